@@ -0,0 +1,67 @@
+// Measures how many NTSC frames worth of CPU time the interpreter can emulate per second.
+// Run with `cargo bench --bench cpu_throughput`.
+
+use std::time::Instant;
+
+use nes_rust::{bus::Bus, cpu::CPU, rom::{checksum::Checksums, Rom, RomMetadata}, Mirroring};
+
+// An NTSC frame is 29780.5 CPU cycles
+const CYCLES_PER_FRAME: usize = 29_781;
+const FRAMES_PER_RUN: usize = 600;
+const RUNS: usize = 5;
+
+// A loop over a page of memory mixing loads, stores, arithmetic, shifts and branches
+const PROGRAM: [u8; 19] = [
+    0xA2, 0x00,       // 8000: LDX #$00
+    0xBD, 0x00, 0x02, // 8002: LDA $0200,X
+    0x69, 0x01,       // 8005: ADC #$01
+    0x9D, 0x00, 0x02, // 8007: STA $0200,X
+    0x0A,             // 800A: ASL A
+    0x45, 0x10,       // 800B: EOR $10
+    0xE8,             // 800D: INX
+    0xD0, 0xF2,       // 800E: BNE $8002
+    0x4C, 0x00, 0x80, // 8010: JMP $8000
+];
+
+fn bench_cpu() -> CPU {
+    let mut prg_rom = vec![0; 0x8000];
+    prg_rom[..PROGRAM.len()].copy_from_slice(&PROGRAM);
+    prg_rom[0x7FFC] = 0x00;
+    prg_rom[0x7FFD] = 0x80;
+
+    let rom = Rom {
+        prg_rom,
+        chr_rom: vec![0; 0x2000],
+        mapper: 0,
+        submapper: 0,
+        screen_mirroring: Mirroring::Horizontal,
+        metadata: RomMetadata::default(),
+        trainer: None,
+        checksums: Checksums::default(),
+        original_header: None,
+    };
+    let mut cpu = CPU::new_with_bus(Bus::new(rom).unwrap());
+    cpu.reset();
+    cpu
+}
+
+fn main() {
+    let mut best = f64::MAX;
+    for _ in 0..RUNS {
+        let mut cpu = bench_cpu();
+        let target = cpu.cycles + FRAMES_PER_RUN * CYCLES_PER_FRAME;
+        let start = Instant::now();
+        while cpu.cycles < target {
+            cpu.step().unwrap();
+        }
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+
+    let frames_per_second = FRAMES_PER_RUN as f64 / best;
+    println!(
+        "cpu_throughput: {:.0} frames/s ({:.1}x real time, {:.2} emulated MHz)",
+        frames_per_second,
+        frames_per_second / 60.0988,
+        frames_per_second * CYCLES_PER_FRAME as f64 / 1_000_000.0,
+    );
+}
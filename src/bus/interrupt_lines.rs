@@ -0,0 +1,70 @@
+// Devices that can hold the IRQ line low. IRQ is level triggered, so the line stays active
+// for as long as any one of these sources is asserting it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IrqSource {
+    Apu = 0b0000_0001,
+    Mapper = 0b0000_0010,
+    External = 0b0000_0100,
+}
+
+#[derive(Default)]
+pub struct InterruptLines {
+    nmi_pending: bool,
+    irq_sources: u8,
+}
+
+impl InterruptLines {
+    pub fn new() -> Self {
+        InterruptLines {
+            nmi_pending: false,
+            irq_sources: 0,
+        }
+    }
+
+    // NMI is edge triggered, so a raised NMI stays pending until the CPU services it
+    pub fn raise_nmi(&mut self) {
+        self.nmi_pending = true;
+    }
+
+    pub fn take_nmi(&mut self) -> bool {
+        let pending = self.nmi_pending;
+        self.nmi_pending = false;
+        pending
+    }
+
+    pub fn set_irq(&mut self, source: IrqSource, active: bool) {
+        match active {
+            true => self.irq_sources |= source as u8,
+            false => self.irq_sources &= !(source as u8),
+        }
+    }
+
+    pub fn is_irq_active(&self) -> bool {
+        self.irq_sources != 0
+    }
+}
+
+#[cfg(test)]
+mod interrupt_lines_tests {
+    use super::*;
+
+    #[test]
+    pub fn nmi_is_cleared_once_taken() {
+        let mut lines = InterruptLines::new();
+        assert!(!lines.take_nmi());
+        lines.raise_nmi();
+        assert!(lines.take_nmi());
+        assert!(!lines.take_nmi());
+    }
+
+    #[test]
+    pub fn irq_stays_active_while_any_source_holds_it() {
+        let mut lines = InterruptLines::new();
+        lines.set_irq(IrqSource::Apu, true);
+        lines.set_irq(IrqSource::Mapper, true);
+        lines.set_irq(IrqSource::Apu, false);
+        assert!(lines.is_irq_active());
+        lines.set_irq(IrqSource::Mapper, false);
+        assert!(!lines.is_irq_active());
+    }
+}
@@ -1,47 +1,147 @@
-use crate::{rom::Rom, MemAccess, ppu::PPU};
+pub mod interrupt_lines;
+pub mod save_file;
+
+use std::{io, path::PathBuf};
+
+use interrupt_lines::{InterruptLines, IrqSource};
+use save_file::{SaveFile, DEFAULT_FLUSH_INTERVAL_FRAMES};
+
+use crate::{error::{ErrorPolicies, ErrorPolicy, NesError, RomError}, mapper::{self, nrom::Nrom, SharedMapper}, rom::Rom, MemAccess, ppu::PPU, Mirroring};
 
 const RAM_START: u16 = 0x0000;
 const RAM_END: u16 = 0x1FFF;
 // const PPU_START: u16 = 0x2000;
 const PPU_END: u16 = 0x3FFF;
-const ROM_START: u16 = 0x8000;
-const ROM_END: u16 = 0xFFFF;
+// APU and controller registers. Neither is emulated yet, so they read as 0 and ignore writes.
+const APU_IO_START: u16 = 0x4000;
+const APU_IO_END: u16 = 0x4017;
+// Everything from here up belongs to the cartridge
+const CARTRIDGE_START: u16 = 0x4020;
+const CARTRIDGE_END: u16 = 0xFFFF;
 
 pub struct Bus {
     pub cpu_vram: [u8; 2048],
-    rom: Rom,
+    pub interrupt_lines: InterruptLines,
+    pub error_policies: ErrorPolicies,
+    mapper: SharedMapper,
     ppu: PPU,
+    // The first error reported under the Stop policy, waiting for the CPU to pick it up
+    pending_error: Option<NesError>,
+    // Set by an OAM DMA, the CPU stalls once the instruction that started it finishes
+    dma_pending: bool,
+    // Only there for cartridges with battery backed RAM, see attach_save_file
+    save_file: Option<SaveFile>,
+    save_interval_frames: u64,
 }
 
 impl Bus {
-    pub fn new(rom: Rom) -> Self {
+    pub fn new(rom: Rom) -> Result<Self, RomError> {
+        Ok(Self::with_mapper(mapper::from_rom(rom)?))
+    }
+
+    pub fn with_mapper(mapper: SharedMapper) -> Self {
         Bus {
             cpu_vram: [0; 2048],
-            ppu: PPU::from_rom(&rom),
-            rom,
+            interrupt_lines: InterruptLines::new(),
+            error_policies: ErrorPolicies::new(),
+            ppu: PPU::new(mapper.clone()),
+            mapper,
+            pending_error: None,
+            dma_pending: false,
+            save_file: None,
+            save_interval_frames: DEFAULT_FLUSH_INTERVAL_FRAMES,
         }
     }
 
     pub fn empty() -> Self {
-        let rom = Rom {
-            prg_rom: vec![0; 0x8000],
-            chr_rom: vec![0; 1024],
-            mapper: 0x0,
-            screen_mirroring: crate::Mirroring::Horizontal
-        };
-        Self::new(rom)
+        let nrom = Nrom::new(vec![0; 0x8000], vec![0; 0x2000], Mirroring::Horizontal);
+        Self::with_mapper(mapper::shared(nrom))
+    }
+
+    // Swaps in a new cartridge, the PPU is rebuilt around it. The old cartridge's save file is flushed and
+    // detached, call attach_save_file again to keep saves for the new one.
+    pub fn load_rom(&mut self, rom: Rom) -> Result<(), RomError> {
+        let mapper = mapper::from_rom(rom)?;
+        self.flush_save()?;
+        self.save_file = None;
+        self.mapper = mapper;
+        self.ppu = PPU::new(self.mapper.clone());
+        Ok(())
+    }
+
+    // Advances the PPU three dots per CPU cycle and the mapper by the CPU cycles, then forwards their
+    // interrupts to the CPU
+    pub fn tick(&mut self, cycles: usize) {
+        let frame_finished = self.ppu.tick(cycles * 3);
+        if let (true, Some(save_file)) = (frame_finished, self.save_file.as_mut()) {
+            if let Err(error) = save_file.frame_finished() {
+                eprintln!("Failed to write save file {}: {error}", save_file.path().display());
+            }
+        }
+        if self.ppu.take_nmi() {
+            self.interrupt_lines.raise_nmi();
+        }
+        self.mapper.borrow_mut().cpu_clock(cycles);
+        let mapper_irq = self.mapper.borrow().irq_active();
+        self.interrupt_lines.set_irq(IrqSource::Mapper, mapper_irq);
     }
 
-    pub fn load_rom(&mut self, rom: Rom) {
-        self.rom = rom;
+    // The current sample of the cartridge's own sound channels, for mixing in with the APU output
+    pub fn expansion_audio(&self) -> f32 {
+        self.mapper.borrow().expansion_audio()
+    }
+
+    // Loads battery backed PRG-RAM from the given file and writes it back there as the game changes it.
+    // Does nothing for cartridges without a battery.
+    pub fn attach_save_file(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        self.save_file = SaveFile::open(path.into(), self.mapper.clone())?;
+        self.set_save_interval(self.save_interval_frames);
+        Ok(())
+    }
+
+    // How many frames the save file waits between flushes
+    pub fn set_save_interval(&mut self, frames: u64) {
+        self.save_interval_frames = frames;
+        if let Some(save_file) = self.save_file.as_mut() {
+            save_file.flush_interval_frames = frames;
+        }
     }
 
-    pub fn read_prg_rom(&self, addr: u16) -> u8 {
-        let mut addr = addr - 0x8000;
-        if self.rom.prg_rom.len() == 0x4000 && addr >= 0x4000 {
-            addr = addr % 0x4000;
+    pub fn flush_save(&mut self) -> io::Result<()> {
+        match self.save_file.as_mut() {
+            Some(save_file) => save_file.flush(),
+            None => Ok(()),
         }
-        self.rom.prg_rom[addr as usize]
+    }
+
+    // Copies $XX00 - $XXFF into OAM starting at OAMADDR
+    fn oam_dma(&mut self, page: u8) {
+        let base = (page as u16) << 8;
+        for offset in 0..=0xFF {
+            let data = self.mem_read(base | offset);
+            self.ppu.write_to_oam_data(data);
+        }
+        self.dma_pending = true;
+    }
+
+    pub fn take_dma(&mut self) -> bool {
+        std::mem::take(&mut self.dma_pending)
+    }
+
+    pub fn report(&mut self, error: NesError) {
+        match self.error_policies.get(error.class()) {
+            ErrorPolicy::Panic => panic!("{error}"),
+            ErrorPolicy::Log => eprintln!("{error}"),
+            ErrorPolicy::Stop => {
+                if self.pending_error.is_none() {
+                    self.pending_error = Some(error);
+                }
+            },
+        }
+    }
+
+    pub fn take_error(&mut self) -> Option<NesError> {
+        self.pending_error.take()
     }
 }
 
@@ -53,17 +153,27 @@ impl MemAccess for Bus {
                 self.cpu_vram[mapped_addr as usize]
             },
             0x2000 | 0x2001 | 0x2003 | 0x2005 | 0x2006 | 0x4014 => {
-                panic!("Attempt to read from write-only PPU register {addr:04X}")
+                self.report(NesError::WriteOnlyRegisterRead { addr });
+                0
             },
+            0x2002 => self.ppu.read_status(),
+            0x2004 => self.ppu.read_oam_data(),
             0x2007 => self.ppu.read_data(),
             0x2008..=PPU_END => {
                 // any attempts at reading PPU data should be done through one of the registers 0x2000 - 0x2007
                 let mirror_down_addr = addr & 0b00100000_00000111;
                 self.mem_read(mirror_down_addr)
             },
-            ROM_START..=ROM_END => self.read_prg_rom(addr),
+            APU_IO_START..=APU_IO_END => 0,
+            CARTRIDGE_START..=CARTRIDGE_END => {
+                let data = self.mapper.borrow_mut().cpu_read(addr);
+                data.unwrap_or_else(|| {
+                    self.report(NesError::UnmappedAccess { addr });
+                    0
+                })
+            },
             _ => {
-                println!("Invalid RAM access at {:#x}", addr);
+                self.report(NesError::UnmappedAccess { addr });
                 0
             }
         }
@@ -76,29 +186,200 @@ impl MemAccess for Bus {
                 self.cpu_vram[mapped_addr as usize] = data;
             },
             0x2000 => self.ppu.write_to_control_register(data),
-            0x2002 => panic!("Attempting to write to read only register 0x2002"),
+            0x2001 => self.ppu.write_to_mask(data),
+            0x2002 => self.report(NesError::ReadOnlyRegisterWrite { addr, data }),
+            0x2003 => self.ppu.write_to_oam_addr(data),
+            0x2004 => self.ppu.write_to_oam_data(data),
+            0x2005 => self.ppu.write_to_scroll(data),
             0x2006 => self.ppu.write_to_ppu_addr(data),
-            0x2007 => self.ppu.write_to_ppu_data(data),
-            0x2008..PPU_END => {
+            0x2007 => {
+                if let Err(error) = self.ppu.write_to_ppu_data(data) {
+                    self.report(error);
+                }
+            },
+            0x4014 => self.oam_dma(data),
+            0x2008..=PPU_END => {
                 // any attempts at writing PPU data should be done through one of the registers 0x2000 - 0x2007
                 let mirror_down_addr = addr & 0b0010_0000_0000_0111;
-                self.mem_read(mirror_down_addr);
+                self.mem_write(mirror_down_addr, data);
             }
-            ROM_START..=ROM_END => {
-                match cfg!(test) {
-                    true => {
-                        let mut addr = addr - 0x8000;
-                        if self.rom.prg_rom.len() == 0x4000 && addr >= 0x4000 {
-                            addr = addr % 0x4000;
-                        }
-                        self.rom.prg_rom[addr as usize] = data;
-                    },
-                    false => panic!("Attempt to write to Cartridge ROM space"),
+            APU_IO_START..=APU_IO_END => (),
+            CARTRIDGE_START..=CARTRIDGE_END => {
+                let result = self.mapper.borrow_mut().cpu_write(addr, data);
+                if let Err(error) = result {
+                    self.report(error);
                 }
             },
-            _ => {
-                println!("Invalid RAM access at {:#x}", addr);
-            }
+            _ => self.report(NesError::UnmappedAccess { addr }),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod bus_tests {
+    use super::*;
+    use crate::{error::ErrorClass, rom::{checksum::Checksums, RomMetadata}};
+
+    #[test]
+    pub fn stop_policy_records_first_error() {
+        let mut bus = Bus::empty();
+        bus.error_policies = ErrorPolicies::all(ErrorPolicy::Stop);
+        assert_eq!(bus.mem_read(0x2000), 0);
+        bus.mem_write(0x2002, 0x10);
+        assert_eq!(bus.take_error(), Some(NesError::WriteOnlyRegisterRead { addr: 0x2000 }));
+        assert_eq!(bus.take_error(), None);
+    }
+
+    #[test]
+    pub fn log_policy_continues() {
+        let mut bus = Bus::empty();
+        bus.error_policies.set(ErrorClass::RegisterAccess, ErrorPolicy::Log);
+        bus.mem_write(0x2002, 0x10);
+        assert_eq!(bus.take_error(), None);
+    }
+
+    #[test]
+    pub fn apu_and_io_registers_are_mapped() {
+        let mut bus = Bus::empty();
+        bus.error_policies = ErrorPolicies::all(ErrorPolicy::Stop);
+        bus.mem_write(0x4000, 0x30);
+        bus.mem_write(0x4017, 0x40);
+        bus.mem_write(0x4016, 1);
+        assert_eq!(bus.mem_read(0x4016), 0);
+        assert_eq!(bus.mem_read(0x4015), 0);
+        assert_eq!(bus.take_error(), None);
+
+        // OAMDMA sits in the middle of the range and is still write only
+        bus.mem_read(0x4014);
+        assert_eq!(bus.take_error(), Some(NesError::WriteOnlyRegisterRead { addr: 0x4014 }));
+    }
+
+    #[test]
+    pub fn ppu_registers_are_routed() {
+        let mut bus = Bus::empty();
+        bus.mem_write(0x2003, 0x10);
+        bus.mem_write(0x2004, 0xAB);
+        bus.mem_write(0x200B, 0x10); // mirror of OAMADDR
+        assert_eq!(bus.mem_read(0x2004), 0xAB);
+
+        bus.ppu.status_register.set_vblank(true);
+        assert_eq!(bus.mem_read(0x3FFA), 0x80); // mirror of PPUSTATUS
+        assert_eq!(bus.mem_read(0x2002), 0x00);
+    }
+
+    #[test]
+    pub fn tick_forwards_vblank_nmi() {
+        let mut bus = Bus::empty();
+        bus.mem_write(0x2000, 0b1000_0000);
+        // Vblank starts on dot 1 of scanline 241, which is dot 82182 of the frame or 27394 CPU cycles in
+        bus.tick(27394);
+        assert!(!bus.interrupt_lines.take_nmi());
+        bus.tick(1);
+        assert!(bus.interrupt_lines.take_nmi());
+        assert_eq!(bus.mem_read(0x2002) & 0x80, 0x80);
+    }
+
+    #[test]
+    pub fn oam_dma_starts_at_oam_addr() {
+        let mut bus = Bus::empty();
+        for offset in 0..=0xFF {
+            bus.mem_write(0x0200 + offset, offset as u8);
+        }
+        bus.mem_write(0x2003, 0x10);
+        bus.mem_write(0x4014, 0x02);
+        assert!(bus.take_dma());
+        assert!(!bus.take_dma());
+        assert_eq!(bus.ppu.oam_data[0x10], 0x00);
+        assert_eq!(bus.ppu.oam_data[0xFF], 0xEF);
+        assert_eq!(bus.ppu.oam_data[0x00], 0xF0);
+        assert_eq!(bus.ppu.oam_data[0x0F], 0xFF);
+    }
+
+    #[test]
+    pub fn cartridge_space_goes_through_mapper() {
+        let mut bus = Bus::empty();
+        bus.error_policies = ErrorPolicies::all(ErrorPolicy::Stop);
+        bus.mem_write(0x8000, 0x42);
+        assert_eq!(bus.mem_read(0x8000), 0x42);
+        assert_eq!(bus.mem_read(0x5000), 0);
+        assert_eq!(bus.take_error(), Some(NesError::UnmappedAccess { addr: 0x5000 }));
+
+        let rom = Rom { prg_rom: vec![0; 0x4000], chr_rom: vec![], mapper: 255, submapper: 0, screen_mirroring: Mirroring::Vertical, metadata: RomMetadata::default(), trainer: None, checksums: Checksums::default(), original_header: None };
+        assert!(Bus::new(rom).is_err());
+    }
+
+    #[test]
+    pub fn tick_clocks_mapper_irq() {
+        let rom = Rom { prg_rom: vec![0; 0x8000], chr_rom: vec![0; 0x2000], mapper: 24, submapper: 0, screen_mirroring: Mirroring::Vertical, metadata: RomMetadata::default(), trainer: None, checksums: Checksums::default(), original_header: None };
+        let mut bus = Bus::new(rom).unwrap();
+        // VRC6 IRQ in CPU cycle mode, 4 cycles before the counter overflows
+        bus.mem_write(0xF000, 0xFC);
+        bus.mem_write(0xF001, 0b110);
+        bus.tick(3);
+        assert!(!bus.interrupt_lines.is_irq_active());
+        bus.tick(1);
+        assert!(bus.interrupt_lines.is_irq_active());
+        bus.mem_write(0xF002, 0);
+        bus.tick(1);
+        assert!(!bus.interrupt_lines.is_irq_active());
+    }
+
+    #[test]
+    pub fn expansion_audio_comes_from_mapper() {
+        let rom = Rom { prg_rom: vec![0; 0x8000], chr_rom: vec![0; 0x2000], mapper: 24, submapper: 0, screen_mirroring: Mirroring::Vertical, metadata: RomMetadata::default(), trainer: None, checksums: Checksums::default(), original_header: None };
+        let mut bus = Bus::new(rom).unwrap();
+        assert_eq!(bus.expansion_audio(), 0.0);
+        // VRC6 pulse 1 in constant volume mode
+        bus.mem_write(0x9000, 0b1000_1111);
+        bus.mem_write(0x9002, 0b1000_0000);
+        bus.tick(1);
+        assert!(bus.expansion_audio() > 0.0);
+        assert!(Bus::empty().expansion_audio() == 0.0);
+    }
+
+    #[test]
+    pub fn battery_ram_is_saved_on_drop() {
+        let path = std::env::temp_dir().join(format!("nes_rust_{}_bus.sav", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let metadata = RomMetadata { has_battery: true, ..RomMetadata::default() };
+        let rom = Rom { prg_rom: vec![0; 0x8000], chr_rom: vec![0; 0x2000], mapper: 0, submapper: 0, screen_mirroring: Mirroring::Vertical, metadata, trainer: None, checksums: Checksums::default(), original_header: None };
+        let mut bus = Bus::new(rom).unwrap();
+        bus.attach_save_file(path.clone()).unwrap();
+        bus.mem_write(0x6000, 0x42);
+        assert_eq!(bus.mem_read(0x6000), 0x42);
+        drop(bus);
+        assert_eq!(std::fs::read(&path).unwrap()[0], 0x42);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    pub fn load_rom_flushes_and_detaches_save() {
+        let path = std::env::temp_dir().join(format!("nes_rust_{}_bus_swap.sav", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let battery_rom = || {
+            let metadata = RomMetadata { has_battery: true, ..RomMetadata::default() };
+            Rom { prg_rom: vec![0; 0x8000], chr_rom: vec![0; 0x2000], mapper: 0, submapper: 0, screen_mirroring: Mirroring::Vertical, metadata, trainer: None, checksums: Checksums::default(), original_header: None }
+        };
+        let mut bus = Bus::new(battery_rom()).unwrap();
+        bus.attach_save_file(path.clone()).unwrap();
+        bus.mem_write(0x6000, 0x42);
+        bus.load_rom(battery_rom()).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[0], 0x42);
+
+        // The new cartridge doesn't write to the old file until it is attached
+        bus.mem_write(0x6000, 0x11);
+        bus.flush_save().unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[0], 0x42);
+        bus.attach_save_file(path.clone()).unwrap();
+        assert_eq!(bus.mem_read(0x6000), 0x42);
+        drop(bus);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[should_panic]
+    pub fn panic_policy_panics() {
+        let mut bus = Bus::empty();
+        bus.mem_read(0x2005);
+    }
+}
@@ -0,0 +1,123 @@
+use std::{fs, io, path::{Path, PathBuf}};
+
+use crate::mapper::SharedMapper;
+
+// Roughly five seconds of play between flushes
+pub const DEFAULT_FLUSH_INTERVAL_FRAMES: u64 = 300;
+
+// The usual place for a save, next to the rom with a .sav extension
+pub fn default_path(rom_path: impl AsRef<Path>) -> PathBuf {
+    rom_path.as_ref().with_extension("sav")
+}
+
+// Keeps battery backed PRG-RAM in sync with a file on disk. The RAM is written out every so many frames
+// if it changed, and once more when the save file is dropped.
+pub struct SaveFile {
+    path: PathBuf,
+    mapper: SharedMapper,
+    pub flush_interval_frames: u64,
+    frames_since_flush: u64,
+}
+
+impl SaveFile {
+    // Loads the RAM from the file if there is one. Returns None when the cartridge has no battery.
+    pub fn open(path: PathBuf, mapper: SharedMapper) -> io::Result<Option<SaveFile>> {
+        {
+            let mut mapper = mapper.borrow_mut();
+            let Some(prg_ram) = mapper.prg_ram().filter(|prg_ram| prg_ram.has_battery()) else {
+                return Ok(None);
+            };
+            match fs::read(&path) {
+                Ok(saved) => prg_ram.load(&saved),
+                // No save yet, the first flush creates it
+                Err(error) if error.kind() == io::ErrorKind::NotFound => (),
+                Err(error) => return Err(error),
+            }
+        }
+        Ok(Some(SaveFile { path, mapper, flush_interval_frames: DEFAULT_FLUSH_INTERVAL_FRAMES, frames_since_flush: 0 }))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn frame_finished(&mut self) -> io::Result<()> {
+        self.frames_since_flush += 1;
+        if self.frames_since_flush < self.flush_interval_frames {
+            return Ok(());
+        }
+        self.flush()
+    }
+
+    // Writes the RAM out if anything changed since the last flush
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.frames_since_flush = 0;
+        let mut mapper = self.mapper.borrow_mut();
+        match mapper.prg_ram().and_then(|prg_ram| prg_ram.take_dirty()) {
+            Some(data) => fs::write(&self.path, data),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for SaveFile {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            eprintln!("Failed to write save file {}: {error}", self.path.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod save_file_tests {
+    use super::*;
+    use crate::{mapper::{nrom::Nrom, shared, PrgRam}, Mirroring};
+
+    fn battery_cart() -> SharedMapper {
+        let mapper = shared(Nrom::new(vec![0; 0x4000], vec![0; 0x2000], Mirroring::Horizontal));
+        *mapper.borrow_mut().prg_ram().unwrap() = PrgRam::new(0x2000, true);
+        mapper
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("nes_rust_{}_{name}.sav", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    pub fn saves_on_interval_and_drop() {
+        let path = temp_path("interval");
+        let mapper = battery_cart();
+        let mut save = SaveFile::open(path.clone(), mapper.clone()).unwrap().unwrap();
+        save.flush_interval_frames = 2;
+
+        mapper.borrow_mut().cpu_write(0x6001, 0x42).unwrap();
+        save.frame_finished().unwrap();
+        assert!(!path.exists());
+        save.frame_finished().unwrap();
+        assert_eq!(fs::read(&path).unwrap()[1], 0x42);
+
+        mapper.borrow_mut().cpu_write(0x6002, 0x43).unwrap();
+        drop(save);
+        assert_eq!(fs::read(&path).unwrap()[2], 0x43);
+
+        // A new session picks up where the last one left off
+        let mapper = battery_cart();
+        let _save = SaveFile::open(path.clone(), mapper.clone()).unwrap().unwrap();
+        assert_eq!(mapper.borrow_mut().cpu_read(0x6001), Some(0x42));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    pub fn carts_without_battery_are_not_saved() {
+        let path = temp_path("no_battery");
+        let mapper = shared(Nrom::new(vec![0; 0x4000], vec![0; 0x2000], Mirroring::Horizontal));
+        assert!(SaveFile::open(path, mapper).unwrap().is_none());
+    }
+
+    #[test]
+    pub fn default_path_replaces_extension() {
+        assert_eq!(default_path("roms/zelda.nes"), PathBuf::from("roms/zelda.sav"));
+    }
+}
@@ -18,16 +18,30 @@ pub enum AddressingMode {
     Relative,
 }
 
+pub fn is_page_crossed(addr1: u16, addr2: u16) -> bool {
+    addr1 & 0xFF00 != addr2 & 0xFF00
+}
+
 impl CPU {
     pub fn get_operand_address(&mut self, mode: &AddressingMode) -> u16 {
-        match mode {
+        let addr = match mode {
             AddressingMode::Immediate => self.program_counter,
             AddressingMode::ZeroPage => self.mem_read(self.program_counter) as u16,
             AddressingMode::ZeroPageX => self.mem_read(self.program_counter).wrapping_add(self.register_x) as u16,
             AddressingMode::ZeroPageY => self.mem_read(self.program_counter).wrapping_add(self.register_y) as u16,
             AddressingMode::Absolute => self.mem_read_u16(self.program_counter),
-            AddressingMode::AbsoluteX => self.mem_read_u16(self.program_counter).wrapping_add(self.register_x as u16),
-            AddressingMode::AbsoluteY => self.mem_read_u16(self.program_counter).wrapping_add(self.register_y as u16),
+            AddressingMode::AbsoluteX => {
+                let base = self.mem_read_u16(self.program_counter);
+                let addr = base.wrapping_add(self.register_x as u16);
+                self.page_crossed = is_page_crossed(base, addr);
+                addr
+            },
+            AddressingMode::AbsoluteY => {
+                let base = self.mem_read_u16(self.program_counter);
+                let addr = base.wrapping_add(self.register_y as u16);
+                self.page_crossed = is_page_crossed(base, addr);
+                addr
+            },
             AddressingMode::Indirect => {
                 let ptr = self.mem_read_u16(self.program_counter);
                 match self.indirect_bug_enabled && (ptr & 0xFF == 0xFF ) {
@@ -52,10 +66,14 @@ impl CPU {
                 let lo = self.mem_read(arg as u16);
                 let hi = self.mem_read(arg.wrapping_add(1) as u16);
                 let ptr_addr = u16::from_le_bytes([lo, hi]);
-                ptr_addr.wrapping_add(self.register_y as u16)
+                let addr = ptr_addr.wrapping_add(self.register_y as u16);
+                self.page_crossed = is_page_crossed(ptr_addr, addr);
+                addr
             },
             _ => panic!("Can't get addr for addressing mode {:?}", mode),
-        }
+        };
+        self.operand_address = Some(addr);
+        addr
     }
 
     /** This function is mostly used by shift / rotate operations that can work directly on the accumulator */
@@ -134,6 +152,11 @@ mod test_addressing_modes {
         cpu.register_x = 0x06;
         cpu.register_y = 0x05;
         assert_eq!(cpu.get_operand_address(&AddressingMode::AbsoluteX), 0x0A0B);
+        assert!(!cpu.page_crossed);
+
+        cpu.register_x = 0xFF;
+        assert_eq!(cpu.get_operand_address(&AddressingMode::AbsoluteX), 0x0B04);
+        assert!(cpu.page_crossed);
     }
 
     #[test]
@@ -117,6 +117,19 @@ impl CPU {
         let result = reg_val.wrapping_sub(param);
         self.status.set_negative_and_zero_flag(result);
     }
+
+    // Unofficial instructions
+    pub fn and_x_subtract(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let param = self.mem_read(addr);
+        let anded = self.register_a & self.register_x;
+        let result = anded.wrapping_sub(param);
+
+        // Flags are set like CMP, the carry flag is not used as a borrow
+        self.status.set_carry_flag(anded >= param);
+        self.status.set_negative_and_zero_flag(result);
+        self.register_x = result;
+    }
 }
 
 #[cfg(test)]
@@ -272,4 +285,16 @@ mod arithmetic_test {
         assert_eq!(cpu.status.0, 0b0010_0101);
     }
 
-}
\ No newline at end of file
+    #[test]
+    pub fn axs_test() {
+        let mut cpu = CPU::new();
+        cpu.register_a = 0xF0;
+        cpu.register_x = 0x3C;
+        cpu.program_counter = 0x8000;
+        cpu.mem_write(0x8000, 0x10);
+        cpu.and_x_subtract(&AddressingMode::Immediate);
+        assert_eq!(cpu.register_x, 0x20);
+        assert_eq!(cpu.register_a, 0xF0);
+        assert_eq!(cpu.status.0, 0b0010_0101);
+    }
+}
@@ -1,4 +1,4 @@
-use super::{addressing_modes::AddressingMode, CPU};
+use super::{addressing_modes::{is_page_crossed, AddressingMode}, CPU};
 use super::MemAccess;
 
 impl CPU {
@@ -45,9 +45,17 @@ impl CPU {
 
     fn branch(&mut self) -> bool {
         let displacement = self.mem_read(self.program_counter) as i8; // cast as an i8 to retain signed value
-        self.program_counter = self.program_counter
-            .wrapping_add(1) // Consumes the current program counter. Make sure not to increment in main cpu cycle body
+        let next_instruction = self.program_counter.wrapping_add(1); // Consumes the current program counter. Make sure not to increment in main cpu cycle body
+        self.program_counter = next_instruction
             .wrapping_add(displacement as u16); // casting to u16 will retain the binary value even when adding
+
+        self.operand_address = Some(self.program_counter);
+
+        // A taken branch costs 1 extra cycle, plus 1 more if it lands on a different page
+        self.cycles += 1;
+        if is_page_crossed(next_instruction, self.program_counter) {
+            self.cycles += 1;
+        }
         true
     }
 
@@ -0,0 +1,125 @@
+use super::status_flags::StatusFlag;
+use super::CPU;
+use crate::MemAccess;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Interrupt {
+    NMI,
+    IRQ,
+    BRK,
+}
+
+impl Interrupt {
+    pub fn vector(&self) -> u16 {
+        match self {
+            Interrupt::NMI => 0xFFFA,
+            Interrupt::IRQ | Interrupt::BRK => 0xFFFE,
+        }
+    }
+}
+
+// Hardware interrupts take the same 7 cycles as a BRK
+pub const INTERRUPT_CYCLES: usize = 7;
+
+impl CPU {
+    // Pushes the program counter and status onto the stack then jumps through the interrupt vector.
+    // Only BRK pushes the status with the B flag set, that's the only way to tell them apart in a handler.
+    pub fn interrupt(&mut self, interrupt: Interrupt) {
+        self.push_stack_u16(self.program_counter);
+
+        let mut status = StatusFlag(self.status.0);
+        status.set_break_flag_1(interrupt == Interrupt::BRK);
+        status.set_break_flag_2(true);
+        self.push_stack(status.0);
+
+        self.status.set_interrupt_flag(true);
+        self.program_counter = self.mem_read_u16(interrupt.vector());
+    }
+
+    pub fn force_break(&mut self) {
+        // BRK is followed by a padding byte, so the return address skips over it
+        self.program_counter = self.program_counter.wrapping_add(1);
+        self.interrupt(Interrupt::BRK);
+    }
+
+    // Checks the interrupt lines before the next instruction is fetched. NMI takes priority over IRQ
+    // and IRQ is ignored while the interrupt disable flag is set.
+    pub(super) fn poll_interrupts(&mut self) -> Option<Interrupt> {
+        let interrupt = if self.bus.interrupt_lines.take_nmi() {
+            Interrupt::NMI
+        } else if self.bus.interrupt_lines.is_irq_active() && !self.status.is_interrupt_set() {
+            Interrupt::IRQ
+        } else {
+            return None;
+        };
+
+        self.interrupt(interrupt);
+        self.cycles += INTERRUPT_CYCLES;
+        Some(interrupt)
+    }
+}
+
+#[cfg(test)]
+mod interrupt_tests {
+    use super::*;
+    use crate::bus::interrupt_lines::IrqSource;
+
+    #[test]
+    pub fn brk_pushes_return_address_and_break_flag() {
+        let mut cpu = CPU::new();
+        cpu.mem_write_u16(0xFFFE, 0x9000);
+        cpu.mem_write(0x9000, 0x02);
+        cpu.load_and_run(vec!(0x00, 0xEA)).unwrap();
+
+        assert_eq!(cpu.program_counter, 0x9001);
+        assert_eq!(cpu.pop_stack(), 0b0011_0100);
+        assert_eq!(cpu.pop_stack_u16(), 0x8002);
+        assert!(cpu.status.is_interrupt_set());
+        assert_eq!(cpu.cycles, 7 + 2);
+    }
+
+    #[test]
+    pub fn brk_and_rti_round_trip() {
+        let mut cpu = CPU::new();
+        cpu.mem_write_u16(0xFFFE, 0x9000);
+        cpu.mem_write(0x9000, 0x40); // RTI
+        // BRK, padding byte, LDX #$05, JAM
+        cpu.load_and_run(vec!(0x00, 0xFF, 0xA2, 0x05, 0x02)).unwrap();
+
+        assert_eq!(cpu.register_x, 0x05);
+        assert_eq!(cpu.program_counter, 0x8005);
+        assert_eq!(cpu.stack_pointer, 0xFD);
+    }
+
+    #[test]
+    pub fn nmi_jumps_through_nmi_vector() {
+        let mut cpu = CPU::new();
+        cpu.mem_write_u16(0xFFFA, 0x9000);
+        cpu.mem_write(0x9000, 0x02);
+        cpu.program_counter = 0x8000;
+        cpu.status.set_interrupt_flag(true); // NMI can't be masked
+        cpu.load(vec!(0xEA, 0x02));
+        cpu.raise_nmi();
+        cpu.run().unwrap();
+
+        // The NMI is serviced once the NOP finishes
+        assert_eq!(cpu.program_counter, 0x9001);
+        assert_eq!(cpu.pop_stack(), 0b0010_0100);
+        assert_eq!(cpu.pop_stack_u16(), 0x8001);
+        assert_eq!(cpu.cycles, 2 + 7 + 2);
+    }
+
+    #[test]
+    pub fn irq_respects_interrupt_disable_flag() {
+        let mut cpu = CPU::new();
+        cpu.mem_write_u16(0xFFFE, 0x9000);
+        cpu.mem_write(0x9000, 0x02);
+        cpu.set_irq(IrqSource::External, true);
+        // SEI is already set after power up so the IRQ waits until CLI
+        cpu.load_and_run(vec!(0xEA, 0x58, 0xEA, 0x02)).unwrap();
+
+        assert_eq!(cpu.program_counter, 0x9001);
+        assert_eq!(cpu.pop_stack(), 0b0010_0000);
+        assert_eq!(cpu.pop_stack_u16(), 0x8002);
+    }
+}
@@ -67,6 +67,37 @@ impl CPU {
         self.register_x = val;
         self.status.set_negative_and_zero_flag(val);
     }
+
+    pub fn load_a_x_and_stack_pointer(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let val = self.mem_read(addr) & self.stack_pointer;
+
+        self.register_a = val;
+        self.register_x = val;
+        self.stack_pointer = val;
+        self.status.set_negative_and_zero_flag(val);
+    }
+
+    // XAA and LXA are unstable on real hardware. A is ORed with a constant that depends on the chip
+    // and temperature before being used, these constants are the ones most NMOS 6502s settle on.
+    pub fn transfer_x_and_a(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let param = self.mem_read(addr);
+        let result = (self.register_a | 0xEE) & self.register_x & param;
+
+        self.register_a = result;
+        self.status.set_negative_and_zero_flag(result);
+    }
+
+    pub fn load_a_and_x_immediate(&mut self, mode: &AddressingMode) {
+        let addr = self.get_operand_address(mode);
+        let param = self.mem_read(addr);
+        let result = (self.register_a | 0xFF) & param;
+
+        self.register_a = result;
+        self.register_x = result;
+        self.status.set_negative_and_zero_flag(result);
+    }
 }
 
 #[cfg(test)]
@@ -75,17 +106,17 @@ mod load_tests {
 
     #[test]
     pub fn lda_zero_flag_status() {
-        let test_program: Vec<u8> = vec!(0xa9, 0x00, 0x00);
+        let test_program: Vec<u8> = vec!(0xa9, 0x00, 0x02);
         let mut cpu = CPU::new();
-        cpu.load_and_run(test_program);
+        cpu.load_and_run(test_program).unwrap();
         assert_eq!(cpu.status.0, 0b0010_0110);
     }
 
     #[test]
     pub fn lda_negative_flag_status() {
-        let test_program: Vec<u8> = vec!(0xa9, 0xc0, 0x00);
+        let test_program: Vec<u8> = vec!(0xa9, 0xc0, 0x02);
         let mut cpu = CPU::new();
-        cpu.load_and_run(test_program);
+        cpu.load_and_run(test_program).unwrap();
         assert_eq!(cpu.status.0, 0b1010_0100);
     }
 
@@ -124,4 +155,42 @@ mod load_tests {
         assert_eq!(cpu.register_a, 0x69);
         assert_eq!(cpu.status.0, 0b0010_0100);
     }
+
+    #[test]
+    pub fn las_test() {
+        let mut cpu = CPU::new();
+        cpu.program_counter = 0x8000;
+        cpu.register_y = 0x02;
+        cpu.mem_write_u16(0x8000, 0x700);
+        cpu.mem_write(0x702, 0x0F);
+        cpu.load_a_x_and_stack_pointer(&AddressingMode::AbsoluteY);
+        assert_eq!(cpu.register_a, 0x0D);
+        assert_eq!(cpu.register_x, 0x0D);
+        assert_eq!(cpu.stack_pointer, 0x0D);
+        assert_eq!(cpu.status.0, 0b0010_0100);
+    }
+
+    #[test]
+    pub fn xaa_test() {
+        let mut cpu = CPU::new();
+        cpu.program_counter = 0x8000;
+        cpu.register_a = 0x00;
+        cpu.register_x = 0xFF;
+        cpu.mem_write(0x8000, 0x0F);
+        cpu.transfer_x_and_a(&AddressingMode::Immediate);
+        assert_eq!(cpu.register_a, 0x0E);
+        assert_eq!(cpu.register_x, 0xFF);
+        assert_eq!(cpu.status.0, 0b0010_0100);
+    }
+
+    #[test]
+    pub fn lxa_test() {
+        let mut cpu = CPU::new();
+        cpu.program_counter = 0x8000;
+        cpu.mem_write(0x8000, 0xA5);
+        cpu.load_a_and_x_immediate(&AddressingMode::Immediate);
+        assert_eq!(cpu.register_a, 0xA5);
+        assert_eq!(cpu.register_x, 0xA5);
+        assert_eq!(cpu.status.0, 0b1010_0100);
+    }
 }
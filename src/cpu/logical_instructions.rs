@@ -87,6 +87,27 @@ impl CPU {
         self.status.set_carry_flag(old_val & 0b0000_0001 != 0);
         self.status.set_negative_and_zero_flag(new_val);
     }
+
+    // Unofficial instructions
+    pub fn and_set_carry(&mut self, mode: &AddressingMode) {
+        self.and(mode);
+        self.status.set_carry_flag(self.status.is_negative_set());
+    }
+
+    pub fn and_shift_right(&mut self, mode: &AddressingMode) {
+        self.and(mode);
+        self.logical_shift_right(&AddressingMode::Accumulator);
+    }
+
+    pub fn and_rotate_right(&mut self, mode: &AddressingMode) {
+        self.and(mode);
+        self.rotate_right(&AddressingMode::Accumulator);
+
+        // Carry is bit 6 of the result and overflow is bit 6 XOR bit 5
+        let result = self.register_a;
+        self.status.set_carry_flag(result & 0b0100_0000 != 0);
+        self.status.set_overflow_flag(result ^ (result << 1));
+    }
 }
 
 #[cfg(test)]
@@ -200,4 +221,45 @@ mod logical_tests {
         assert_eq!(cpu.mem_read(0x70), 0);
         assert_eq!(cpu.status.0, 0b0010_0110);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn anc_test() {
+        let mut cpu = CPU::new();
+        cpu.register_a = 0b1100_0000;
+        cpu.program_counter = 0x8000;
+        cpu.mem_write(0x8000, 0b1000_0001);
+        cpu.and_set_carry(&AddressingMode::Immediate);
+        assert_eq!(cpu.register_a, 0b1000_0000);
+        assert_eq!(cpu.status.0, 0b1010_0101);
+    }
+
+    #[test]
+    pub fn alr_test() {
+        let mut cpu = CPU::new();
+        cpu.register_a = 0xFF;
+        cpu.program_counter = 0x8000;
+        cpu.mem_write(0x8000, 0b0000_0011);
+        cpu.and_shift_right(&AddressingMode::Immediate);
+        assert_eq!(cpu.register_a, 0b0000_0001);
+        assert_eq!(cpu.status.0, 0b0010_0101);
+    }
+
+    #[test]
+    pub fn arr_test() {
+        let mut cpu = CPU::new();
+        cpu.register_a = 0xFF;
+        cpu.program_counter = 0x8000;
+        cpu.status.set_carry_flag(true);
+        cpu.mem_write(0x8000, 0b1000_0000);
+        cpu.and_rotate_right(&AddressingMode::Immediate);
+        assert_eq!(cpu.register_a, 0b1100_0000);
+        assert_eq!(cpu.status.0, 0b1110_0101);
+
+        cpu.register_a = 0xFF;
+        cpu.status.set_carry_flag(false);
+        cpu.mem_write(0x8000, 0xFF);
+        cpu.and_rotate_right(&AddressingMode::Immediate);
+        assert_eq!(cpu.register_a, 0b0111_1111);
+        assert_eq!(cpu.status.0, 0b0010_0101);
+    }
+}
@@ -7,13 +7,16 @@ mod rmw_instructions;
 pub mod addressing_modes;
 mod stack;
 mod status_flags;
+pub mod interrupts;
+pub mod step;
 pub mod opcodes;
 pub mod snake;
 
-use opcodes::OP_CODE_REF_TABLE;
 use status_flags::StatusFlag;
 
-use crate::{bus::Bus, rom::Rom, MemAccess};
+use std::{io, path::PathBuf};
+
+use crate::{bus::{interrupt_lines::IrqSource, Bus}, error::{ErrorClass, ErrorPolicy, NesError, RomError}, rom::Rom, MemAccess};
 
 pub struct CPU {
     pub register_a: u8,
@@ -22,8 +25,17 @@ pub struct CPU {
     pub status: StatusFlag,
     pub program_counter: u16,
     pub stack_pointer: u8,
+    pub cycles: usize,
+    // Set by the JAM opcode. A halted CPU stops fetching instructions until it is reset.
+    pub halted: bool,
     bus: Bus,
 
+    // Set by get_operand_address when an indexed address lands on a different page than its base address.
+    // Read instructions take an extra cycle when this happens.
+    page_crossed: bool,
+    // The last address resolved by get_operand_address, reported back through StepResult
+    operand_address: Option<u16>,
+
     // The JMP Indirect instruction has a bug where fetches on addrress 0xXXFF would return the MSB from
     // 0xXX00 instead of (0xXXFF + 1) (ie XX + 1). For example AAFF would have MSB at AA00 instead of AB00.
     pub indirect_bug_enabled: bool,
@@ -61,7 +73,11 @@ impl CPU {
             status: StatusFlag(0b0010_0100),
             program_counter: 0,
             stack_pointer: 0xFD,
+            cycles: 0,
+            halted: false,
             indirect_bug_enabled: false,
+            page_crossed: false,
+            operand_address: None,
             bus: Bus::empty(),
         }
     }
@@ -74,8 +90,8 @@ impl CPU {
         self._load(program, 0x8000);
     }
 
-    pub fn load_rom(&mut self, rom: Rom) {
-        self.bus.load_rom(rom);
+    pub fn load_rom(&mut self, rom: Rom) -> Result<(), RomError> {
+        self.bus.load_rom(rom)
     }
 
     fn _load(&mut self, program: Vec<u8>, starting_pos: u16) {
@@ -85,106 +101,48 @@ impl CPU {
         }
     } 
 
-    pub fn run(&mut self) {
+    pub fn run(&mut self) -> Result<(), NesError> {
         // Calls run with callback with an empty function
-        self.run_with_callback(|_| ());
+        self.run_with_callback(|_| ())
+    }
+
+    pub fn raise_nmi(&mut self) {
+        self.bus.interrupt_lines.raise_nmi();
+    }
+
+    pub fn set_irq(&mut self, source: IrqSource, active: bool) {
+        self.bus.interrupt_lines.set_irq(source, active);
+    }
+
+    pub fn set_error_policy(&mut self, class: ErrorClass, policy: ErrorPolicy) {
+        self.bus.error_policies.set(class, policy);
+    }
+
+    // Battery backed RAM is loaded from and saved to this file, see Bus::attach_save_file
+    pub fn attach_save_file(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        self.bus.attach_save_file(path)
+    }
+
+    pub fn set_save_interval(&mut self, frames: u64) {
+        self.bus.set_save_interval(frames);
     }
 
-    pub fn run_with_callback<F> (&mut self, mut callback: F) 
+    pub fn flush_save(&mut self) -> io::Result<()> {
+        self.bus.flush_save()
+    }
+
+    pub fn expansion_audio(&self) -> f32 {
+        self.bus.expansion_audio()
+    }
+
+    // Runs until the CPU halts or an error is raised under the Stop policy
+    pub fn run_with_callback<F> (&mut self, mut callback: F) -> Result<(), NesError>
     where F: FnMut(&mut CPU) {
-        loop {
+        while !self.halted {
             callback(self);
-            let op_code = self.mem_read(self.program_counter);
-            let op_code_params = OP_CODE_REF_TABLE.get(&op_code)
-                .expect(&format!("${op_code:#x} is not a valid operation"));
-            // println!("Program counter {:#x} doing {:#x} {} {:?}", self.program_counter, op_code, op_code_params.instruction, op_code_params.addressing_mode);
-            self.program_counter += 1;
-            match op_code_params.instruction {
-                "ADC" => self.add_with_carry(&op_code_params.addressing_mode),
-                "AND" => self.and(&op_code_params.addressing_mode),
-                "ASL" => self.arithmetic_shift_left(&op_code_params.addressing_mode),
-                "BCC" => if self.branch_if_carry_clear() { continue; },
-                "BCS" => if self.branch_if_carry_set() { continue; },
-                "BEQ" => if self.branch_if_equal() { continue; },
-                "BIT" => self.bit_test(&op_code_params.addressing_mode),
-                "BMI" => if self.branch_if_minus() { continue; },
-                "BNE" => if self.branch_if_not_equal() { continue; },
-                "BPL" => if self.branch_if_positive() { continue; },
-                "BVC" => if self.branch_if_overflow_clear() { continue; },
-                "BVS" => if self.branch_if_overflow_set() { continue; },
-                "CLC" => self.status.set_carry_flag(false),
-                "CLD" => self.status.set_decimal_flag(false),
-                "CLI" => self.status.set_interrupt_flag(false),
-                "CLV" => self.status.set_overflow_flag(0),
-                "CMP" => self.compare(&op_code_params.addressing_mode),
-                "CPX" => self.compare_x(&op_code_params.addressing_mode),
-                "CPY" => self.compare_y(&op_code_params.addressing_mode),
-                "DCP" => self.decrement_compare_a(&op_code_params.addressing_mode),
-                "DEC" => self.decrement_mem(&op_code_params.addressing_mode),
-                "DEX" => self.decrement_x(),
-                "DEY" => self.decrement_y(),
-                "EOR" => self.exclusive_or(&op_code_params.addressing_mode),
-                "IGN" => { 
-                    let op_addr = self.get_operand_address(&op_code_params.addressing_mode);
-                    self.mem_read(op_addr);
-                },
-                "INC" => self.increment_mem(&op_code_params.addressing_mode),
-                "INX" => self.increment_x(),
-                "INY" => self.increment_y(),
-                "ISC" => self.increment_subtract_carry(&op_code_params.addressing_mode),
-                "JMP" => {
-                    self.jump(&op_code_params.addressing_mode);
-                    continue;
-                },
-                "JSR" => {
-                    self.jump_subroutine();
-                    continue;
-                },
-                "LAX" => self.load_a_and_x(&op_code_params.addressing_mode),
-                "LDA" => self.load_register_a(&op_code_params.addressing_mode),
-                "LDX" => self.load_register_x(&op_code_params.addressing_mode),
-                "LDY" => self.load_register_y(&op_code_params.addressing_mode),
-                "LSR" => self.logical_shift_right(&op_code_params.addressing_mode),
-                "NOP" => (),
-                "ORA" => self.inclusive_or(&op_code_params.addressing_mode),
-                "PHA" => self.push_stack(self.register_a),
-                "PHP" => self.push_processor_status(),
-                "PLA" => self.pull_accumulator(),
-                "PLP" => self.pull_processor_status(),
-                "RLA" => self.rotate_left_and_a(&op_code_params.addressing_mode),
-                "ROL" => self.rotate_left(&op_code_params.addressing_mode),
-                "ROR" => self.rotate_right(&op_code_params.addressing_mode),
-                "RRA" => self.rotate_right_add_a(&op_code_params.addressing_mode),
-                "RTI" => {
-                    self.return_from_interrupt();
-                    continue;
-                },
-                "SAX" => self.store_a_anded_x(&op_code_params.addressing_mode),
-                "SBC" => self.subtract_with_carry(&op_code_params.addressing_mode),
-                "SEC" => self.set_carry_flag(),
-                "SED" => self.set_decimal_flag(),
-                "SEI" => self.set_interrupt_flag(),
-                "SKB" => {
-                    let op_addr = self.get_operand_address(&op_code_params.addressing_mode);
-                    self.mem_read(op_addr);
-                },
-                "SLO" => self.shift_left_or_a(&op_code_params.addressing_mode),
-                "SRE" => self.shift_right_eor_a(&op_code_params.addressing_mode),
-                "STA" => self.store_register_a(&op_code_params.addressing_mode),
-                "STX" => self.store_register_x(&op_code_params.addressing_mode),
-                "STY" => self.store_register_y(&op_code_params.addressing_mode),
-                "TAX" => self.transfer_a_to_x(),
-                "TAY" => self.transfer_a_to_y(),
-                "TSX" => self.transfer_stack_pointer_to_x(),
-                "TXA" => self.transfer_x_to_a(),
-                "TXS" => self.transfer_x_to_stack_pointer(),
-                "TYA" => self.transfer_y_to_a(),
-                "RTS" => self.return_subroutine(),
-                "BRK" => return,
-                _=> println!("TODO for ${op_code:#x}"), 
-            }
-            self.program_counter += op_code_params.bytes - 1;
-       }
+            self.step()?;
+        }
+        Ok(())
     }
 
     pub fn reset(&mut self) {
@@ -194,12 +152,15 @@ impl CPU {
         self.status = StatusFlag(0b0010_0100);
         self.program_counter = self.mem_read_u16(0xFFFC);
         self.stack_pointer = 0xFD;
+        self.halted = false;
+        // The reset sequence takes 7 cycles before the first instruction is fetched
+        self.cycles = 7;
     }
 
-    pub fn load_and_run(&mut self, program: Vec<u8>) {
+    pub fn load_and_run(&mut self, program: Vec<u8>) -> Result<(), NesError> {
         self.load(program);
         self.program_counter = self.mem_read_u16(0xFFFC);
-        self.run();
+        self.run()
     }
 }
 
@@ -209,9 +170,9 @@ mod cpu_tests {
 
     #[test]
     pub fn simple_program() {
-        let test_program: Vec<u8> = vec!(0xa9, 0x15, 0xaa, 0xe8, 0x00);
+        let test_program: Vec<u8> = vec!(0xa9, 0x15, 0xaa, 0xe8, 0x02);
         let mut cpu = CPU::new();
-        cpu.load_and_run(test_program);
+        cpu.load_and_run(test_program).unwrap();
         assert_eq!(cpu.register_a, 0x15);
         assert_eq!(cpu.register_x, 0x16);
         assert_eq!(cpu.status.0, 0b0010_0100);
@@ -221,7 +182,7 @@ mod cpu_tests {
     #[test]
     pub fn sta_stx_sty_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0x25, 0xA2, 0x35, 0xA0, 0x45, 0x85, 0x15, 0x86, 0x25, 0x84, 0x35, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0x25, 0xA2, 0x35, 0xA0, 0x45, 0x85, 0x15, 0x86, 0x25, 0x84, 0x35, 0x02)).unwrap();
 
         assert_eq!(cpu.program_counter, 0x800D);
         assert_eq!(cpu.mem_read(0x15), 0x25);
@@ -233,7 +194,7 @@ mod cpu_tests {
     pub fn adc_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write(0x70, 33);
-        cpu.load_and_run(vec!(0xA9, 120, 0x65, 0x70, 0x0));
+        cpu.load_and_run(vec!(0xA9, 120, 0x65, 0x70, 0x02)).unwrap();
 
         assert_eq!(cpu.program_counter, 0x8005);
         assert_eq!(cpu.register_a, 153);
@@ -243,7 +204,7 @@ mod cpu_tests {
     #[test]
     pub fn and_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0b0001111, 0x29, 0b11111010, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0b0001111, 0x29, 0b11111010, 0x02)).unwrap();
 
         assert_eq!(cpu.register_a, 0b00001010);
         assert_eq!(cpu.program_counter, 0x8005);
@@ -252,7 +213,7 @@ mod cpu_tests {
     #[test]
     pub fn asl_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0b1011_0001, 0x0A, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0b1011_0001, 0x0A, 0x02)).unwrap();
 
         assert_eq!(cpu.register_a, 0b0110_0010);
         assert_eq!(cpu.program_counter, 0x8004);
@@ -261,32 +222,38 @@ mod cpu_tests {
     #[test]
     pub fn bcc_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0x90, 0b1111_1011, 0x00)); // subtracts 5 from PC to get back to BRK command before 0x8000
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0x90, 0b1111_1101)); // subtracts 3 from PC to get back to the JAM at 0x8000
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x7FFE);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
 
     #[test]
     pub fn bcs_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0b10000000, 0x0A, 0xB0, 0b1111_1010, 0x00)); // subtracts 6 from PC to get back to BRK command at 0x8000
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0xA9, 0b10000000, 0x0A, 0xB0, 0b1111_1010)); // subtracts 6 from PC to get back to the JAM at 0x8000
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x8000);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
 
     #[test]
     pub fn beq_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0x00, 0xF0, 0b1111_1011, 0x00));
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0xA9, 0x00, 0xF0, 0b1111_1011));
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x8000);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
 
     #[test]
     pub fn bit_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write(0xABAB, 0b1101_1010);
-        cpu.load_and_run(vec!(0xA9, 0x0F, 0x2C, 0xAB, 0xAB, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0x0F, 0x2C, 0xAB, 0xAB, 0x02)).unwrap();
 
         assert_eq!(cpu.status.0, 0b1110_0100);
     }
@@ -294,49 +261,59 @@ mod cpu_tests {
     #[test]
     pub fn bmi_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0xCC, 0x30, 0b1111_1011, 0x00));
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0xA9, 0xCC, 0x30, 0b1111_1011));
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x8000);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
 
     #[test]
     pub fn bne_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0x01, 0xD0, 0b1111_1011, 0x00));
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0xA9, 0x01, 0xD0, 0b1111_1011));
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x8000);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
 
     #[test]
     pub fn bpl_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0x00, 0x10, 0b1111_1011, 0x00));
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0xA9, 0x00, 0x10, 0b1111_1011));
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x8000);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
 
     #[test]
     pub fn bvc_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write(0xAB, 0b1011_0000);
-        cpu.load_and_run(vec!(0xA9, 0x00, 0x24, 0xAB, 0x50, (7 as i8).wrapping_neg() as u8, 0x00));
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0xA9, 0x00, 0x24, 0xAB, 0x50, (7 as i8).wrapping_neg() as u8));
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x8000);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
 
     #[test]
     pub fn bvs_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write(0xAB, 0b1111_0000);
-        cpu.load_and_run(vec!(0xA9, 0x00, 0x24, 0xAB, 0x70, (7 as i8).wrapping_neg() as u8, 0x00));
+        cpu.program_counter = 0x8001;
+        cpu.load(vec!(0x02, 0xA9, 0x00, 0x24, 0xAB, 0x70, (7 as i8).wrapping_neg() as u8));
+        cpu.run().unwrap();
 
-        assert_eq!(cpu.program_counter, 0x8000);
+        assert_eq!(cpu.program_counter, 0x8001);
     }
     
     #[test]
     pub fn clc_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0b1011_0001, 0x0A, 0x18, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0b1011_0001, 0x0A, 0x18, 0x02)).unwrap();
 
         assert_eq!(cpu.status.0, 0b0010_0100);
         assert_eq!(cpu.program_counter, 0x8005);
@@ -347,8 +324,8 @@ mod cpu_tests {
         let mut cpu = CPU::new();
         cpu.status.0 = 0xFF;
         cpu.program_counter = 0x8000;
-        cpu.load(vec!(0xD8, 0x00));
-        cpu.run();
+        cpu.load(vec!(0xD8, 0x02));
+        cpu.run().unwrap();
 
         assert_eq!(cpu.status.0, 0b1111_0111);
         assert_eq!(cpu.program_counter, 0x8002);
@@ -359,8 +336,8 @@ mod cpu_tests {
         let mut cpu = CPU::new();
         cpu.status.0 = 0xFF;
         cpu.program_counter = 0x8000;
-        cpu.load(vec!(0x58, 0x00));
-        cpu.run();
+        cpu.load(vec!(0x58, 0x02));
+        cpu.run().unwrap();
         
         assert_eq!(cpu.status.0, 0b1111_1011);
         assert_eq!(cpu.program_counter, 0x8002);
@@ -371,8 +348,8 @@ mod cpu_tests {
         let mut cpu = CPU::new();
         cpu.status.0 = 0xFF;
         cpu.program_counter = 0x8000;
-        cpu.load(vec!(0xB8, 0x00));
-        cpu.run();
+        cpu.load(vec!(0xB8, 0x02));
+        cpu.run().unwrap();
         
         assert_eq!(cpu.status.0, 0b1011_1111);
         assert_eq!(cpu.program_counter, 0x8002);
@@ -382,7 +359,7 @@ mod cpu_tests {
     pub fn cmp_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write_u16(0x7000, 0x15);
-        cpu.load_and_run(vec!(0xA9, 0xA0, 0xCD, 0x00, 0x70, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0xA0, 0xCD, 0x00, 0x70, 0x02)).unwrap();
 
         assert_eq!(cpu.program_counter, 0x8006);
         assert_eq!(cpu.status.0, 0b1010_0101);
@@ -394,8 +371,8 @@ mod cpu_tests {
         cpu.register_x = 0x15;
         cpu.program_counter = 0x8000;
         cpu.mem_write_u16(0x700, 0x15);
-        cpu.load(vec!(0xEC, 0x0, 0x7, 0x00));
-        cpu.run();
+        cpu.load(vec!(0xEC, 0x0, 0x7, 0x02));
+        cpu.run().unwrap();
 
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.status.0, 0b0010_0111);
@@ -407,8 +384,8 @@ mod cpu_tests {
         cpu.register_y = 0xAB;
         cpu.program_counter = 0x8000;
         cpu.mem_write_u16(0x700, 0xA0);
-        cpu.load(vec!(0xCC, 0x0, 0x7, 0x00));
-        cpu.run();
+        cpu.load(vec!(0xCC, 0x0, 0x7, 0x02));
+        cpu.run().unwrap();
 
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.status.0, 0b0010_0101);
@@ -418,7 +395,7 @@ mod cpu_tests {
     pub fn dec_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write(0x700, 155);
-        cpu.load_and_run(vec!(0xCE, 0x0, 0x7, 0x00));
+        cpu.load_and_run(vec!(0xCE, 0x0, 0x7, 0x02)).unwrap();
 
         assert_eq!(cpu.mem_read(0x700), 154);
         assert_eq!(cpu.status.0, 0b1010_0100);
@@ -428,7 +405,7 @@ mod cpu_tests {
     #[test]
     pub fn dex_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xCA, 0x00));
+        cpu.load_and_run(vec!(0xCA, 0x02)).unwrap();
         assert_eq!(cpu.register_x, 0xFF);
         assert_eq!(cpu.status.0, 0b1010_0100);
         assert_eq!(cpu.program_counter, 0x8002);
@@ -439,8 +416,8 @@ mod cpu_tests {
         let mut cpu = CPU::new();
         cpu.register_y = 0x1;
         cpu.program_counter = 0x8000;
-        cpu.load(vec!(0x88, 0x00));
-        cpu.run();
+        cpu.load(vec!(0x88, 0x02));
+        cpu.run().unwrap();
         assert_eq!(cpu.register_y, 0);
         assert_eq!(cpu.status.0, 0b0010_0110);
         assert_eq!(cpu.program_counter, 0x8002);
@@ -449,7 +426,7 @@ mod cpu_tests {
     #[test]
     pub fn eor_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0xFF, 0x49, 0b1010_1010, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0xFF, 0x49, 0b1010_1010, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8005);
         assert_eq!(cpu.register_a, 0b0101_0101);
         assert_eq!(cpu.status.0, 0b0010_0100);
@@ -459,7 +436,7 @@ mod cpu_tests {
     pub fn inc_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write(0x700, 0xD2);
-        cpu.load_and_run(vec!(0xEE, 0x0, 0x7, 0x00));
+        cpu.load_and_run(vec!(0xEE, 0x0, 0x7, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.mem_read(0x700), 0xD3);
         assert_eq!(cpu.status.0, 0b1010_0100);
@@ -468,7 +445,7 @@ mod cpu_tests {
     #[test]
     pub fn iny_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA0, 210, 0xC8, 0x00));
+        cpu.load_and_run(vec!(0xA0, 210, 0xC8, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.register_y, 211);
         assert_eq!(cpu.status.0, 0b1010_0100);
@@ -477,7 +454,7 @@ mod cpu_tests {
     #[test]
     pub fn ldx_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA2, 0xFF, 0x00));
+        cpu.load_and_run(vec!(0xA2, 0xFF, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8003);
         assert_eq!(cpu.register_x, 0xFF);
         assert_eq!(cpu.status.0, 0b1010_0100);
@@ -486,7 +463,7 @@ mod cpu_tests {
     #[test]
     pub fn ldy_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA0, 0x32, 0x00));
+        cpu.load_and_run(vec!(0xA0, 0x32, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8003);
         assert_eq!(cpu.register_y, 0x32);
         assert_eq!(cpu.status.0, 0b0010_0100);
@@ -496,14 +473,15 @@ mod cpu_tests {
     pub fn jmp_instruction() {
         let mut cpu = CPU::new();
         cpu.mem_write_u16(0x700, 0xABCD);
-        cpu.load_and_run(vec!(0x4C, 0x05, 0x80, 0x00, 0x00, 0x6C, 0x00, 0x7, 0x00));
+        cpu.mem_write(0xABCD, 0x02);
+        cpu.load_and_run(vec!(0x4C, 0x05, 0x80, 0x00, 0x00, 0x6C, 0x00, 0x7, 0x00)).unwrap();
         assert_eq!(cpu.program_counter, 0xABCE);
     }
 
     #[test]
     pub fn jsr_rts_instructions() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0x20, 0x06, 0x80, 0xA2, 0x69, 0x00, 0xA0, 0xDC, 0x60, 0x0));
+        cpu.load_and_run(vec!(0x20, 0x06, 0x80, 0xA2, 0x69, 0x02, 0xA0, 0xDC, 0x60, 0x00)).unwrap();
         assert_eq!(cpu.program_counter, 0x8006);
         assert_eq!(cpu.register_x, 0x69);
         assert_eq!(cpu.register_y, 0xDC);
@@ -512,7 +490,7 @@ mod cpu_tests {
     #[test]
     pub fn lsr_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0b0000_0001, 0x4A, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0b0000_0001, 0x4A, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.register_a, 0);
         assert_eq!(cpu.status.0, 0b0010_0111);
@@ -521,14 +499,14 @@ mod cpu_tests {
     #[test]
     pub fn nop_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xEA, 0xEA, 0x00));
+        cpu.load_and_run(vec!(0xEA, 0xEA, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8003);
     }
 
     #[test]
     pub fn ora_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0b1000_0001, 0x09, 0b0001_1000, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0b1000_0001, 0x09, 0b0001_1000, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8005);
         assert_eq!(cpu.register_a, 0b1001_1001);
         assert_eq!(cpu.status.0, 0b1010_0100);
@@ -537,7 +515,7 @@ mod cpu_tests {
     #[test]
     pub fn pha_pla_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0xF0, 0x48, 0x69, 0x5, 0x68, 0x00));
+        cpu.load_and_run(vec!(0xA9, 0xF0, 0x48, 0x69, 0x5, 0x68, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8007);
         assert_eq!(cpu.mem_read(0x1FD), 0xF0);
         assert_eq!(cpu.register_a, 0xF0);
@@ -547,7 +525,7 @@ mod cpu_tests {
     #[test]
     pub fn php_plp_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0xFF, 0x08, 0x69, 0x10, 0x28, 0x0));
+        cpu.load_and_run(vec!(0xA9, 0xFF, 0x08, 0x69, 0x10, 0x28, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8007);
         assert_eq!(cpu.status.0, 0b1010_0100);
         assert_eq!(cpu.mem_read(0x1FD), 0b1010_0100);
@@ -556,7 +534,7 @@ mod cpu_tests {
     #[test]
     pub fn rol_ror_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0b11000011, 0x2A, 0x2A, 0x6A, 00));
+        cpu.load_and_run(vec!(0xA9, 0b11000011, 0x2A, 0x2A, 0x6A, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8006);
         assert_eq!(cpu.register_a, 0b1000_0110);
         assert_eq!(cpu.status.0, 0b1010_0101);
@@ -568,8 +546,9 @@ mod cpu_tests {
         cpu.program_counter = 0x8000;
         cpu.push_stack_u16( 0x8050);
         cpu.push_stack( 0b1000_0010);
+        cpu.mem_write(0x8050, 0x02);
         cpu.load(vec!(0x40));
-        cpu.run();
+        cpu.run().unwrap();
         assert_eq!(cpu.program_counter, 0x8051);
         assert_eq!(cpu.status.0, 0b1010_0010);
         assert_eq!(cpu.stack_pointer, 0xFD);
@@ -578,7 +557,7 @@ mod cpu_tests {
     #[test]
     pub fn sbc_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 0, 0xE9, 10, 0x0));
+        cpu.load_and_run(vec!(0xA9, 0, 0xE9, 10, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8005);
         assert_eq!(cpu.status.0, 0b1010_0100);
         assert_eq!(cpu.register_a, 245);
@@ -587,7 +566,7 @@ mod cpu_tests {
     #[test]
     pub fn sec_sed_sei_instructions() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0x38, 0xF8, 0x78, 0x0));
+        cpu.load_and_run(vec!(0x38, 0xF8, 0x78, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.status.0, 0b0010_1101);
     }
@@ -595,7 +574,7 @@ mod cpu_tests {
     #[test]
     pub fn tax_tay_instructions() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA9, 200, 0xAA, 0xA8, 0x0));
+        cpu.load_and_run(vec!(0xA9, 200, 0xAA, 0xA8, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8005);
         assert_eq!(cpu.status.0, 0b1010_0100);
         assert_eq!(cpu.register_a, 200);
@@ -606,7 +585,7 @@ mod cpu_tests {
     #[test]
     pub fn tsx_txs_instructions() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA2, 200, 0x9A, 0xE8, 0xBA, 0x0));
+        cpu.load_and_run(vec!(0xA2, 200, 0x9A, 0xE8, 0xBA, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8006);
         assert_eq!(cpu.status.0, 0b1010_0100);
         assert_eq!(cpu.register_x, 200);
@@ -616,7 +595,7 @@ mod cpu_tests {
     #[test]
     pub fn txa_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA2, 0xFF, 0x8A, 0x00));
+        cpu.load_and_run(vec!(0xA2, 0xFF, 0x8A, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.status.0, 0b1010_0100);
         assert_eq!(cpu.register_x, 0xFF);
@@ -626,13 +605,60 @@ mod cpu_tests {
     #[test]
     pub fn tya_instruction() {
         let mut cpu = CPU::new();
-        cpu.load_and_run(vec!(0xA0, 0xFF, 0x98, 0x00));
+        cpu.load_and_run(vec!(0xA0, 0xFF, 0x98, 0x02)).unwrap();
         assert_eq!(cpu.program_counter, 0x8004);
         assert_eq!(cpu.status.0, 0b1010_0100);
         assert_eq!(cpu.register_y, 0xFF);
         assert_eq!(cpu.register_a, 0xFF);
     }
 
+    #[test]
+    pub fn cycle_count() {
+        let mut cpu = CPU::new();
+        // LDA #$01 (2), TAX (2), NOP (2), JAM (2)
+        cpu.load_and_run(vec!(0xA9, 0x01, 0xAA, 0xEA, 0x02)).unwrap();
+        assert_eq!(cpu.cycles, 8);
+    }
+
+    #[test]
+    pub fn page_cross_cycle_penalty() {
+        let mut cpu = CPU::new();
+        // LDX #$01 (2), LDA $00FF,X (4 + 1), STA $00FF,X (5), JAM (2)
+        cpu.load_and_run(vec!(0xA2, 0x01, 0xBD, 0xFF, 0x00, 0x9D, 0xFF, 0x00, 0x02)).unwrap();
+        assert_eq!(cpu.cycles, 14);
+
+        let mut cpu = CPU::new();
+        // LDY #$01 (2), LDA ($10),Y (5 + 1), JAM (2)
+        cpu.mem_write_u16(0x10, 0x02FF);
+        cpu.load_and_run(vec!(0xA0, 0x01, 0xB1, 0x10, 0x02)).unwrap();
+        assert_eq!(cpu.cycles, 10);
+    }
+
+    #[test]
+    pub fn branch_cycle_penalty() {
+        let mut cpu = CPU::new();
+        // BCC not taken (2), JAM (2)
+        cpu.status.set_carry_flag(true);
+        cpu.program_counter = 0x8000;
+        cpu.load(vec!(0x90, 0x01, 0x02));
+        cpu.run().unwrap();
+        assert_eq!(cpu.cycles, 4);
+
+        let mut cpu = CPU::new();
+        // BCC taken on the same page (2 + 1), JAM (2)
+        cpu.load_and_run(vec!(0x90, 0x01, 0xEA, 0x02)).unwrap();
+        assert_eq!(cpu.cycles, 5);
+
+        let mut cpu = CPU::new();
+        // BCC taken onto the next page (2 + 2), JAM (2)
+        cpu.program_counter = 0x80FD;
+        cpu.mem_write(0x80FD, 0x90);
+        cpu.mem_write(0x80FE, 0x01);
+        cpu.mem_write(0x8100, 0x02);
+        cpu.run().unwrap();
+        assert_eq!(cpu.cycles, 6);
+    }
+
     // ------------------------------------------------------------
     // END OF INSTRUCTION TEST SECTION
     // ------------------------------------------------------------
@@ -659,5 +685,25 @@ mod cpu_tests {
         assert_eq!(cpu.register_x, 0);
         assert_eq!(cpu.status.0, 0b0010_0100);
         assert_eq!(cpu.program_counter, 0xABCD);
+        assert_eq!(cpu.cycles, 7);
+    }
+
+    #[test]
+    pub fn stop_policy_returns_error() {
+        let mut cpu = CPU::new();
+        cpu.set_error_policy(ErrorClass::RegisterAccess, ErrorPolicy::Stop);
+        let result = cpu.load_and_run(vec!(0xA9, 0x01, 0x8D, 0x02, 0x20, 0xE8, 0x02));
+        assert_eq!(result, Err(NesError::ReadOnlyRegisterWrite { addr: 0x2002, data: 0x01 }));
+        // Execution stops after the instruction that raised the error
+        assert_eq!(cpu.program_counter, 0x8005);
+        assert_eq!(cpu.register_x, 0);
+    }
+
+    #[test]
+    pub fn log_policy_continues() {
+        let mut cpu = CPU::new();
+        cpu.set_error_policy(ErrorClass::RegisterAccess, ErrorPolicy::Log);
+        cpu.load_and_run(vec!(0xA9, 0x01, 0x8D, 0x02, 0x20, 0xE8, 0x02)).unwrap();
+        assert_eq!(cpu.register_x, 1);
     }
 }
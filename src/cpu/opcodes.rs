@@ -1,365 +1,649 @@
-use phf::phf_map;
+use std::fmt;
 
 use super::addressing_modes::AddressingMode;
+use super::CPU;
+use crate::MemAccess;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mnemonic {
+    ADC,
+    AHX,
+    ALR,
+    ANC,
+    AND,
+    ARR,
+    ASL,
+    AXS,
+    BCC,
+    BCS,
+    BEQ,
+    BIT,
+    BMI,
+    BNE,
+    BPL,
+    BRK,
+    BVC,
+    BVS,
+    CLC,
+    CLD,
+    CLI,
+    CLV,
+    CMP,
+    CPX,
+    CPY,
+    DCP,
+    DEC,
+    DEX,
+    DEY,
+    EOR,
+    IGN,
+    INC,
+    INX,
+    INY,
+    ISC,
+    JAM,
+    JMP,
+    JSR,
+    LAS,
+    LAX,
+    LDA,
+    LDX,
+    LDY,
+    LSR,
+    LXA,
+    NOP,
+    ORA,
+    PHA,
+    PHP,
+    PLA,
+    PLP,
+    RLA,
+    ROL,
+    ROR,
+    RRA,
+    RTI,
+    RTS,
+    SAX,
+    SBC,
+    SEC,
+    SED,
+    SEI,
+    SHX,
+    SHY,
+    SKB,
+    SLO,
+    SRE,
+    STA,
+    STX,
+    STY,
+    TAS,
+    TAX,
+    TAY,
+    TSX,
+    TXA,
+    TXS,
+    TYA,
+    XAA,
+}
+
+impl fmt::Display for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+// Executes an instruction. Returns true when the handler moved the program counter itself,
+// in which case the main body must not skip over the operand bytes.
+pub type Handler = fn(&mut CPU, &AddressingMode) -> bool;
+
+impl Mnemonic {
+    pub fn is_branch(&self) -> bool {
+        matches!(
+            self,
+            Mnemonic::BCC | Mnemonic::BCS | Mnemonic::BEQ | Mnemonic::BMI
+                | Mnemonic::BNE | Mnemonic::BPL | Mnemonic::BVC | Mnemonic::BVS
+        )
+    }
+
+    const fn handler(self) -> Handler {
+        match self {
+            Mnemonic::ADC => |cpu, mode| { cpu.add_with_carry(mode); false },
+            Mnemonic::AND => |cpu, mode| { cpu.and(mode); false },
+            Mnemonic::ASL => |cpu, mode| { cpu.arithmetic_shift_left(mode); false },
+            Mnemonic::BCC => |cpu, _| cpu.branch_if_carry_clear(),
+            Mnemonic::BCS => |cpu, _| cpu.branch_if_carry_set(),
+            Mnemonic::BEQ => |cpu, _| cpu.branch_if_equal(),
+            Mnemonic::BIT => |cpu, mode| { cpu.bit_test(mode); false },
+            Mnemonic::BMI => |cpu, _| cpu.branch_if_minus(),
+            Mnemonic::BNE => |cpu, _| cpu.branch_if_not_equal(),
+            Mnemonic::BPL => |cpu, _| cpu.branch_if_positive(),
+            Mnemonic::BRK => |cpu, _| { cpu.force_break(); true },
+            Mnemonic::BVC => |cpu, _| cpu.branch_if_overflow_clear(),
+            Mnemonic::BVS => |cpu, _| cpu.branch_if_overflow_set(),
+            Mnemonic::CLC => |cpu, _| { cpu.status.set_carry_flag(false); false },
+            Mnemonic::CLD => |cpu, _| { cpu.status.set_decimal_flag(false); false },
+            Mnemonic::CLI => |cpu, _| { cpu.status.set_interrupt_flag(false); false },
+            Mnemonic::CLV => |cpu, _| { cpu.status.set_overflow_flag(0); false },
+            Mnemonic::CMP => |cpu, mode| { cpu.compare(mode); false },
+            Mnemonic::CPX => |cpu, mode| { cpu.compare_x(mode); false },
+            Mnemonic::CPY => |cpu, mode| { cpu.compare_y(mode); false },
+            Mnemonic::DCP => |cpu, mode| { cpu.decrement_compare_a(mode); false },
+            Mnemonic::DEC => |cpu, mode| { cpu.decrement_mem(mode); false },
+            Mnemonic::DEX => |cpu, _| { cpu.decrement_x(); false },
+            Mnemonic::DEY => |cpu, _| { cpu.decrement_y(); false },
+            Mnemonic::EOR => |cpu, mode| { cpu.exclusive_or(mode); false },
+            Mnemonic::INC => |cpu, mode| { cpu.increment_mem(mode); false },
+            Mnemonic::INX => |cpu, _| { cpu.increment_x(); false },
+            Mnemonic::INY => |cpu, _| { cpu.increment_y(); false },
+            Mnemonic::ISC => |cpu, mode| { cpu.increment_subtract_carry(mode); false },
+            Mnemonic::JAM => |cpu, _| { cpu.halted = true; false },
+            Mnemonic::JMP => |cpu, mode| { cpu.jump(mode); true },
+            Mnemonic::JSR => |cpu, _| { cpu.jump_subroutine(); true },
+            Mnemonic::LAX => |cpu, mode| { cpu.load_a_and_x(mode); false },
+            Mnemonic::LDA => |cpu, mode| { cpu.load_register_a(mode); false },
+            Mnemonic::LDX => |cpu, mode| { cpu.load_register_x(mode); false },
+            Mnemonic::LDY => |cpu, mode| { cpu.load_register_y(mode); false },
+            Mnemonic::LSR => |cpu, mode| { cpu.logical_shift_right(mode); false },
+            Mnemonic::NOP => |_, _| false,
+            Mnemonic::ORA => |cpu, mode| { cpu.inclusive_or(mode); false },
+            Mnemonic::PHA => |cpu, _| { cpu.push_stack(cpu.register_a); false },
+            Mnemonic::PHP => |cpu, _| { cpu.push_processor_status(); false },
+            Mnemonic::PLA => |cpu, _| { cpu.pull_accumulator(); false },
+            Mnemonic::PLP => |cpu, _| { cpu.pull_processor_status(); false },
+            Mnemonic::RLA => |cpu, mode| { cpu.rotate_left_and_a(mode); false },
+            Mnemonic::ROL => |cpu, mode| { cpu.rotate_left(mode); false },
+            Mnemonic::ROR => |cpu, mode| { cpu.rotate_right(mode); false },
+            Mnemonic::RRA => |cpu, mode| { cpu.rotate_right_add_a(mode); false },
+            Mnemonic::RTI => |cpu, _| { cpu.return_from_interrupt(); true },
+            Mnemonic::RTS => |cpu, _| { cpu.return_subroutine(); false },
+            Mnemonic::SAX => |cpu, mode| { cpu.store_a_anded_x(mode); false },
+            Mnemonic::SBC => |cpu, mode| { cpu.subtract_with_carry(mode); false },
+            Mnemonic::SEC => |cpu, _| { cpu.set_carry_flag(); false },
+            Mnemonic::SED => |cpu, _| { cpu.set_decimal_flag(); false },
+            Mnemonic::SEI => |cpu, _| { cpu.set_interrupt_flag(); false },
+            Mnemonic::SLO => |cpu, mode| { cpu.shift_left_or_a(mode); false },
+            Mnemonic::SRE => |cpu, mode| { cpu.shift_right_eor_a(mode); false },
+            Mnemonic::STA => |cpu, mode| { cpu.store_register_a(mode); false },
+            Mnemonic::STX => |cpu, mode| { cpu.store_register_x(mode); false },
+            Mnemonic::STY => |cpu, mode| { cpu.store_register_y(mode); false },
+            Mnemonic::TAX => |cpu, _| { cpu.transfer_a_to_x(); false },
+            Mnemonic::TAY => |cpu, _| { cpu.transfer_a_to_y(); false },
+            Mnemonic::TSX => |cpu, _| { cpu.transfer_stack_pointer_to_x(); false },
+            Mnemonic::TXA => |cpu, _| { cpu.transfer_x_to_a(); false },
+            Mnemonic::TXS => |cpu, _| { cpu.transfer_x_to_stack_pointer(); false },
+            Mnemonic::TYA => |cpu, _| { cpu.transfer_y_to_a(); false },
+            Mnemonic::AHX => |cpu, mode| { cpu.store_a_anded_x_high_byte(mode); false },
+            Mnemonic::ALR => |cpu, mode| { cpu.and_shift_right(mode); false },
+            Mnemonic::ANC => |cpu, mode| { cpu.and_set_carry(mode); false },
+            Mnemonic::ARR => |cpu, mode| { cpu.and_rotate_right(mode); false },
+            Mnemonic::AXS => |cpu, mode| { cpu.and_x_subtract(mode); false },
+            Mnemonic::LAS => |cpu, mode| { cpu.load_a_x_and_stack_pointer(mode); false },
+            Mnemonic::LXA => |cpu, mode| { cpu.load_a_and_x_immediate(mode); false },
+            Mnemonic::SHX => |cpu, mode| { cpu.store_x_high_byte(mode); false },
+            Mnemonic::SHY => |cpu, mode| { cpu.store_y_high_byte(mode); false },
+            Mnemonic::TAS => |cpu, mode| { cpu.transfer_a_anded_x_to_stack_pointer(mode); false },
+            Mnemonic::XAA => |cpu, mode| { cpu.transfer_x_and_a(mode); false },
+            // Unofficial NOPs that still perform the read of their addressing mode
+            Mnemonic::IGN | Mnemonic::SKB => |cpu, mode| {
+                let addr = cpu.get_operand_address(mode);
+                cpu.mem_read(addr);
+                false
+            },
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct OpCode {
-    pub instruction: &'static str,
+    pub mnemonic: Mnemonic,
     pub cycles: u8,
     pub bytes: u16,
     pub addressing_mode: AddressingMode,
+    pub handler: Handler,
+    // Indexed reads take an extra cycle when the effective address crosses a page boundary.
+    // Stores and read-modify-write instructions always spend that cycle, so it's already in their base count.
+    pub page_cross_penalty: bool,
 }
 
 impl OpCode {
-    pub const fn new(instruction: &'static str, bytes: u16, cycles: u8, addressing_mode: AddressingMode) -> Self {
-        OpCode{ instruction, cycles, bytes, addressing_mode}
+    pub const fn new(mnemonic: Mnemonic, bytes: u16, cycles: u8, addressing_mode: AddressingMode) -> Self {
+        let indexed = matches!(
+            addressing_mode,
+            AddressingMode::AbsoluteX | AddressingMode::AbsoluteY | AddressingMode::IndirectY
+        );
+        let page_cross_penalty = indexed && matches!(
+            mnemonic,
+            Mnemonic::ADC | Mnemonic::AND | Mnemonic::CMP | Mnemonic::EOR | Mnemonic::LDA | Mnemonic::LDX
+                | Mnemonic::LDY | Mnemonic::ORA | Mnemonic::SBC | Mnemonic::LAX | Mnemonic::IGN | Mnemonic::LAS
+        );
+        OpCode{ mnemonic, cycles, bytes, addressing_mode, handler: mnemonic.handler(), page_cross_penalty }
     }
 }
 
-pub static OP_CODE_REF_TABLE: phf::Map<u8, OpCode> = phf_map! {
+// Builds the 256 entry decode table at compile time. Op codes that aren't listed stay None.
+macro_rules! op_code_table {
+    ($($op_code:literal => ($mnemonic:ident, $bytes:literal, $cycles:literal, $mode:ident),)*) => {{
+        let mut table: [Option<OpCode>; 256] = [const { None }; 256];
+        $(table[$op_code] = Some(OpCode::new(Mnemonic::$mnemonic, $bytes, $cycles, AddressingMode::$mode));)*
+        table
+    }};
+}
+
+pub fn decode(op_code: u8) -> Option<&'static OpCode> {
+    OP_CODE_REF_TABLE[op_code as usize].as_ref()
+}
+
+pub static OP_CODE_REF_TABLE: [Option<OpCode>; 256] = op_code_table! {
     // ADC
-    0x69u8 => OpCode::new("ADC", 2, 2, AddressingMode::Immediate),
-    0x65u8 => OpCode::new("ADC", 2, 3, AddressingMode::ZeroPage),
-    0x75u8 => OpCode::new("ADC", 2, 4, AddressingMode::ZeroPageX),
-    0x6Du8 => OpCode::new("ADC", 3, 4, AddressingMode::Absolute),
-    0x7Du8 => OpCode::new("ADC", 3, 4, AddressingMode::AbsoluteX),
-    0x79u8 => OpCode::new("ADC", 3, 4, AddressingMode::AbsoluteY),
-    0x61u8 => OpCode::new("ADC", 2, 6, AddressingMode::IndirectX),
-    0x71u8 => OpCode::new("ADC", 2, 5, AddressingMode::IndirectY),
+    0x69 => (ADC, 2, 2, Immediate),
+    0x65 => (ADC, 2, 3, ZeroPage),
+    0x75 => (ADC, 2, 4, ZeroPageX),
+    0x6D => (ADC, 3, 4, Absolute),
+    0x7D => (ADC, 3, 4, AbsoluteX),
+    0x79 => (ADC, 3, 4, AbsoluteY),
+    0x61 => (ADC, 2, 6, IndirectX),
+    0x71 => (ADC, 2, 5, IndirectY),
 
     // AND
-    0x29u8 => OpCode::new("AND", 2, 2, AddressingMode::Immediate),
-    0x25u8 => OpCode::new("AND", 2, 3, AddressingMode::ZeroPage),
-    0x35u8 => OpCode::new("AND", 2, 4, AddressingMode::ZeroPageX),
-    0x2Du8 => OpCode::new("AND", 3, 4, AddressingMode::Absolute),
-    0x3Du8 => OpCode::new("AND", 3, 4, AddressingMode::AbsoluteX),
-    0x39u8 => OpCode::new("AND", 3, 4, AddressingMode::AbsoluteY),
-    0x21u8 => OpCode::new("AND", 2, 6, AddressingMode::IndirectX),
-    0x31u8 => OpCode::new("AND", 2, 5, AddressingMode::IndirectY),
+    0x29 => (AND, 2, 2, Immediate),
+    0x25 => (AND, 2, 3, ZeroPage),
+    0x35 => (AND, 2, 4, ZeroPageX),
+    0x2D => (AND, 3, 4, Absolute),
+    0x3D => (AND, 3, 4, AbsoluteX),
+    0x39 => (AND, 3, 4, AbsoluteY),
+    0x21 => (AND, 2, 6, IndirectX),
+    0x31 => (AND, 2, 5, IndirectY),
 
     // ASL
-    0x0Au8 => OpCode::new("ASL", 1, 2, AddressingMode::Accumulator),
-    0x06u8 => OpCode::new("ASL", 2, 5, AddressingMode::ZeroPage),
-    0x16u8 => OpCode::new("ASL", 2, 6, AddressingMode::ZeroPageX),
-    0x0Eu8 => OpCode::new("ASL", 3, 6, AddressingMode::Absolute),
-    0x1Eu8 => OpCode::new("ASL", 3, 7, AddressingMode::AbsoluteX),
+    0x0A => (ASL, 1, 2, Accumulator),
+    0x06 => (ASL, 2, 5, ZeroPage),
+    0x16 => (ASL, 2, 6, ZeroPageX),
+    0x0E => (ASL, 3, 6, Absolute),
+    0x1E => (ASL, 3, 7, AbsoluteX),
 
     // Branching
-    0x90u8 => OpCode::new("BCC", 2, 2, AddressingMode::Relative),
-    0xB0u8 => OpCode::new("BCS", 2, 2, AddressingMode::Relative),
-    0xF0u8 => OpCode::new("BEQ", 2, 2, AddressingMode::Relative),
-    0x30u8 => OpCode::new("BMI", 2, 2, AddressingMode::Relative),
-    0xD0u8 => OpCode::new("BNE", 2, 2, AddressingMode::Relative),
-    0x10u8 => OpCode::new("BPL", 2, 2, AddressingMode::Relative),
-    0x50u8 => OpCode::new("BVC", 2, 2, AddressingMode::Relative),
-    0x70u8 => OpCode::new("BVS", 2, 2, AddressingMode::Relative),
+    0x90 => (BCC, 2, 2, Relative),
+    0xB0 => (BCS, 2, 2, Relative),
+    0xF0 => (BEQ, 2, 2, Relative),
+    0x30 => (BMI, 2, 2, Relative),
+    0xD0 => (BNE, 2, 2, Relative),
+    0x10 => (BPL, 2, 2, Relative),
+    0x50 => (BVC, 2, 2, Relative),
+    0x70 => (BVS, 2, 2, Relative),
 
     // BIT
-    0x24u8 => OpCode::new("BIT", 2, 3, AddressingMode::ZeroPage),
-    0x2Cu8 => OpCode::new("BIT", 3, 4, AddressingMode::Absolute),
+    0x24 => (BIT, 2, 3, ZeroPage),
+    0x2C => (BIT, 3, 4, Absolute),
 
     // Flag Controls
-    0x18u8 => OpCode::new("CLC", 1, 2, AddressingMode::Implied),
-    0xD8u8 => OpCode::new("CLD", 1, 2, AddressingMode::Implied),
-    0x58u8 => OpCode::new("CLI", 1, 2, AddressingMode::Implied),
-    0xB8u8 => OpCode::new("CLV", 1, 2, AddressingMode::Implied),
+    0x18 => (CLC, 1, 2, Implied),
+    0xD8 => (CLD, 1, 2, Implied),
+    0x58 => (CLI, 1, 2, Implied),
+    0xB8 => (CLV, 1, 2, Implied),
 
     // CMP
-    0xC9u8 => OpCode::new("CMP", 2, 2, AddressingMode::Immediate),
-    0xC5u8 => OpCode::new("CMP", 2, 3, AddressingMode::ZeroPage),
-    0xD5u8 => OpCode::new("CMP", 2, 4, AddressingMode::ZeroPageX),
-    0xCDu8 => OpCode::new("CMP", 3, 4, AddressingMode::Absolute),
-    0xDDu8 => OpCode::new("CMP", 3, 4, AddressingMode::AbsoluteX),
-    0xD9u8 => OpCode::new("CMP", 3, 4, AddressingMode::AbsoluteY),
-    0xC1u8 => OpCode::new("CMP", 2, 6, AddressingMode::IndirectX),
-    0xD1u8 => OpCode::new("CMP", 2, 5, AddressingMode::IndirectY),
+    0xC9 => (CMP, 2, 2, Immediate),
+    0xC5 => (CMP, 2, 3, ZeroPage),
+    0xD5 => (CMP, 2, 4, ZeroPageX),
+    0xCD => (CMP, 3, 4, Absolute),
+    0xDD => (CMP, 3, 4, AbsoluteX),
+    0xD9 => (CMP, 3, 4, AbsoluteY),
+    0xC1 => (CMP, 2, 6, IndirectX),
+    0xD1 => (CMP, 2, 5, IndirectY),
 
     // CPX
-    0xE0u8 => OpCode::new("CPX", 2, 2, AddressingMode::Immediate),
-    0xE4u8 => OpCode::new("CPX", 2, 3, AddressingMode::ZeroPage),
-    0xEcu8 => OpCode::new("CPX", 3, 4, AddressingMode::Absolute),
+    0xE0 => (CPX, 2, 2, Immediate),
+    0xE4 => (CPX, 2, 3, ZeroPage),
+    0xEC => (CPX, 3, 4, Absolute),
 
     // CPY
-    0xC0u8 => OpCode::new("CPY", 2, 2, AddressingMode::Immediate),
-    0xC4u8 => OpCode::new("CPY", 2, 3, AddressingMode::ZeroPage),
-    0xCCu8 => OpCode::new("CPY", 3, 4, AddressingMode::Absolute),
+    0xC0 => (CPY, 2, 2, Immediate),
+    0xC4 => (CPY, 2, 3, ZeroPage),
+    0xCC => (CPY, 3, 4, Absolute),
 
     // DEC
-    0xC6u8 => OpCode::new("DEC", 2, 5, AddressingMode::ZeroPage),
-    0xD6u8 => OpCode::new("DEC", 2, 6, AddressingMode::ZeroPageX),
-    0xCEu8 => OpCode::new("DEC", 3, 6, AddressingMode::Absolute),
-    0xDEu8 => OpCode::new("DEC", 3, 7, AddressingMode::AbsoluteX),
+    0xC6 => (DEC, 2, 5, ZeroPage),
+    0xD6 => (DEC, 2, 6, ZeroPageX),
+    0xCE => (DEC, 3, 6, Absolute),
+    0xDE => (DEC, 3, 7, AbsoluteX),
 
     // DEX
-    0xCAu8 => OpCode::new("DEX", 1, 2, AddressingMode::Implied),
+    0xCA => (DEX, 1, 2, Implied),
 
     // DEY
-    0x88u8 => OpCode::new("DEY", 1, 2, AddressingMode::Implied),
+    0x88 => (DEY, 1, 2, Implied),
 
     // EOR
-    0x49u8 => OpCode::new("EOR", 2, 2, AddressingMode::Immediate),
-    0x45u8 => OpCode::new("EOR", 2, 3, AddressingMode::ZeroPage),
-    0x55u8 => OpCode::new("EOR", 2, 4, AddressingMode::ZeroPageX),
-    0x4Du8 => OpCode::new("EOR", 3, 4, AddressingMode::Absolute),
-    0x5Du8 => OpCode::new("EOR", 3, 4, AddressingMode::AbsoluteX),
-    0x59u8 => OpCode::new("EOR", 3, 4, AddressingMode::AbsoluteY),
-    0x41u8 => OpCode::new("EOR", 2, 6, AddressingMode::IndirectX),
-    0x51u8 => OpCode::new("EOR", 2, 5, AddressingMode::IndirectY),
+    0x49 => (EOR, 2, 2, Immediate),
+    0x45 => (EOR, 2, 3, ZeroPage),
+    0x55 => (EOR, 2, 4, ZeroPageX),
+    0x4D => (EOR, 3, 4, Absolute),
+    0x5D => (EOR, 3, 4, AbsoluteX),
+    0x59 => (EOR, 3, 4, AbsoluteY),
+    0x41 => (EOR, 2, 6, IndirectX),
+    0x51 => (EOR, 2, 5, IndirectY),
 
     // INC
-    0xE6u8 => OpCode::new("INC", 2, 5, AddressingMode::ZeroPage),
-    0xF6u8 => OpCode::new("INC", 2, 6, AddressingMode::ZeroPageX),
-    0xEEu8 => OpCode::new("INC", 3, 6, AddressingMode::Absolute),
-    0xFEu8 => OpCode::new("INC", 3, 7, AddressingMode::AbsoluteX),
+    0xE6 => (INC, 2, 5, ZeroPage),
+    0xF6 => (INC, 2, 6, ZeroPageX),
+    0xEE => (INC, 3, 6, Absolute),
+    0xFE => (INC, 3, 7, AbsoluteX),
 
     // INX
-    0xE8u8 => OpCode::new("INX", 1, 2, AddressingMode::Implied),
+    0xE8 => (INX, 1, 2, Implied),
 
     // INY
-    0xC8u8 => OpCode::new("INY", 1, 2, AddressingMode::Implied),
+    0xC8 => (INY, 1, 2, Implied),
 
     // JMP
-    0x4Cu8 => OpCode::new("JMP", 3, 3, AddressingMode::Absolute),
-    0x6Cu8 => OpCode::new("JMP", 3, 5, AddressingMode::Indirect),
+    0x4C => (JMP, 3, 3, Absolute),
+    0x6C => (JMP, 3, 5, Indirect),
 
     // JSR
-    0x20u8 => OpCode::new("JSR", 3, 6, AddressingMode::Absolute),
+    0x20 => (JSR, 3, 6, Absolute),
 
     // LDA
-    0xA9u8 => OpCode::new("LDA", 2, 2, AddressingMode::Immediate),
-    0xA5u8 => OpCode::new("LDA", 2, 3, AddressingMode::ZeroPage),
-    0xB5u8 => OpCode::new("LDA", 2, 4, AddressingMode::ZeroPageX),
-    0xADu8 => OpCode::new("LDA", 3, 4, AddressingMode::Absolute),
-    0xBDu8 => OpCode::new("LDA", 3, 4, AddressingMode::AbsoluteX),
-    0xB9u8 => OpCode::new("LDA", 3, 4, AddressingMode::AbsoluteY),
-    0xA1u8 => OpCode::new("LDA", 2, 6, AddressingMode::IndirectX),
-    0xB1u8 => OpCode::new("LDA", 2, 5, AddressingMode::IndirectY),
+    0xA9 => (LDA, 2, 2, Immediate),
+    0xA5 => (LDA, 2, 3, ZeroPage),
+    0xB5 => (LDA, 2, 4, ZeroPageX),
+    0xAD => (LDA, 3, 4, Absolute),
+    0xBD => (LDA, 3, 4, AbsoluteX),
+    0xB9 => (LDA, 3, 4, AbsoluteY),
+    0xA1 => (LDA, 2, 6, IndirectX),
+    0xB1 => (LDA, 2, 5, IndirectY),
 
     // LDX
-    0xA2u8 => OpCode::new("LDX", 2, 2, AddressingMode::Immediate),
-    0xA6u8 => OpCode::new("LDX", 2, 3, AddressingMode::ZeroPage),
-    0xB6u8 => OpCode::new("LDX", 2, 4, AddressingMode::ZeroPageY),
-    0xAEu8 => OpCode::new("LDX", 3, 4, AddressingMode::Absolute),
-    0xBEu8 => OpCode::new("LDX", 3, 4, AddressingMode::AbsoluteY),
+    0xA2 => (LDX, 2, 2, Immediate),
+    0xA6 => (LDX, 2, 3, ZeroPage),
+    0xB6 => (LDX, 2, 4, ZeroPageY),
+    0xAE => (LDX, 3, 4, Absolute),
+    0xBE => (LDX, 3, 4, AbsoluteY),
 
     // LDY
-    0xA0u8 => OpCode::new("LDY", 2, 2, AddressingMode::Immediate),
-    0xA4u8 => OpCode::new("LDY", 2, 3, AddressingMode::ZeroPage),
-    0xB4u8 => OpCode::new("LDY", 2, 4, AddressingMode::ZeroPageX),
-    0xACu8 => OpCode::new("LDY", 3, 4, AddressingMode::Absolute),
-    0xBCu8 => OpCode::new("LDY", 3, 4, AddressingMode::AbsoluteX),
+    0xA0 => (LDY, 2, 2, Immediate),
+    0xA4 => (LDY, 2, 3, ZeroPage),
+    0xB4 => (LDY, 2, 4, ZeroPageX),
+    0xAC => (LDY, 3, 4, Absolute),
+    0xBC => (LDY, 3, 4, AbsoluteX),
 
     // LSR
-    0x4Au8 => OpCode::new("LSR", 1, 2, AddressingMode::Accumulator),
-    0x46u8 => OpCode::new("LSR", 2, 5, AddressingMode::ZeroPage),
-    0x56u8 => OpCode::new("LSR", 2, 6, AddressingMode::ZeroPageX),
-    0x4Eu8 => OpCode::new("LSR", 3, 6, AddressingMode::Absolute),
-    0x5Eu8 => OpCode::new("LSR", 3, 7, AddressingMode::AbsoluteX),
+    0x4A => (LSR, 1, 2, Accumulator),
+    0x46 => (LSR, 2, 5, ZeroPage),
+    0x56 => (LSR, 2, 6, ZeroPageX),
+    0x4E => (LSR, 3, 6, Absolute),
+    0x5E => (LSR, 3, 7, AbsoluteX),
 
     // NOP
-    0xEAu8 => OpCode::new("NOP", 1, 2, AddressingMode::Implied),
+    0xEA => (NOP, 1, 2, Implied),
 
     // ORA
-    0x09u8 => OpCode::new("ORA", 2, 2, AddressingMode::Immediate),
-    0x05u8 => OpCode::new("ORA", 2, 3, AddressingMode::ZeroPage),
-    0x15u8 => OpCode::new("ORA", 2, 4, AddressingMode::ZeroPageX),
-    0x0Du8 => OpCode::new("ORA", 3, 4, AddressingMode::Absolute),
-    0x1Du8 => OpCode::new("ORA", 3, 4, AddressingMode::AbsoluteX),
-    0x19u8 => OpCode::new("ORA", 3, 4, AddressingMode::AbsoluteY),
-    0x01u8 => OpCode::new("ORA", 2, 6, AddressingMode::IndirectX),
-    0x11u8 => OpCode::new("ORA", 2, 5, AddressingMode::IndirectY),
+    0x09 => (ORA, 2, 2, Immediate),
+    0x05 => (ORA, 2, 3, ZeroPage),
+    0x15 => (ORA, 2, 4, ZeroPageX),
+    0x0D => (ORA, 3, 4, Absolute),
+    0x1D => (ORA, 3, 4, AbsoluteX),
+    0x19 => (ORA, 3, 4, AbsoluteY),
+    0x01 => (ORA, 2, 6, IndirectX),
+    0x11 => (ORA, 2, 5, IndirectY),
 
     // PHA
-    0x48u8 => OpCode::new("PHA", 1, 3, AddressingMode::Implied),
+    0x48 => (PHA, 1, 3, Implied),
 
     // PHP
-    0x08u8 => OpCode::new("PHP", 1, 3, AddressingMode::Implied),
+    0x08 => (PHP, 1, 3, Implied),
 
     // PLA
-    0x68u8 => OpCode::new("PLA", 1, 4, AddressingMode::Implied),
+    0x68 => (PLA, 1, 4, Implied),
 
     // PLP
-    0x28u8 => OpCode::new("PLP", 1, 4, AddressingMode::Implied),
+    0x28 => (PLP, 1, 4, Implied),
 
     // ROL
-    0x2Au8 => OpCode::new("ROL", 1, 2, AddressingMode::Accumulator),
-    0x26u8 => OpCode::new("ROL", 2, 5, AddressingMode::ZeroPage),
-    0x36u8 => OpCode::new("ROL", 2, 6, AddressingMode::ZeroPageX),
-    0x2Eu8 => OpCode::new("ROL", 3, 6, AddressingMode::Absolute),
-    0x3Eu8 => OpCode::new("ROL", 3, 7, AddressingMode::AbsoluteX),
+    0x2A => (ROL, 1, 2, Accumulator),
+    0x26 => (ROL, 2, 5, ZeroPage),
+    0x36 => (ROL, 2, 6, ZeroPageX),
+    0x2E => (ROL, 3, 6, Absolute),
+    0x3E => (ROL, 3, 7, AbsoluteX),
 
     // ROR
-    0x6Au8 => OpCode::new("ROR", 1, 2, AddressingMode::Accumulator),
-    0x66u8 => OpCode::new("ROR", 2, 5, AddressingMode::ZeroPage),
-    0x76u8 => OpCode::new("ROR", 2, 6, AddressingMode::ZeroPageX),
-    0x6Eu8 => OpCode::new("ROR", 3, 6, AddressingMode::Absolute),
-    0x7Eu8 => OpCode::new("ROR", 3, 7, AddressingMode::AbsoluteX),
+    0x6A => (ROR, 1, 2, Accumulator),
+    0x66 => (ROR, 2, 5, ZeroPage),
+    0x76 => (ROR, 2, 6, ZeroPageX),
+    0x6E => (ROR, 3, 6, Absolute),
+    0x7E => (ROR, 3, 7, AbsoluteX),
 
     // RTI
-    0x40u8 => OpCode::new("RTI", 1, 6, AddressingMode::Implied),
+    0x40 => (RTI, 1, 6, Implied),
 
     // RTS
-    0x60u8 => OpCode::new("RTS", 1, 6, AddressingMode::Implied),
+    0x60 => (RTS, 1, 6, Implied),
 
     // SBC
-    0xE9u8 => OpCode::new("SBC", 2, 2, AddressingMode::Immediate),
-    0xE5u8 => OpCode::new("SBC", 2, 3, AddressingMode::ZeroPage),
-    0xF5u8 => OpCode::new("SBC", 2, 4, AddressingMode::ZeroPageX),
-    0xEDu8 => OpCode::new("SBC", 3, 4, AddressingMode::Absolute),
-    0xFDu8 => OpCode::new("SBC", 3, 4, AddressingMode::AbsoluteX),
-    0xF9u8 => OpCode::new("SBC", 3, 4, AddressingMode::AbsoluteY),
-    0xE1u8 => OpCode::new("SBC", 2, 6, AddressingMode::IndirectX),
-    0xF1u8 => OpCode::new("SBC", 2, 5, AddressingMode::IndirectY),
+    0xE9 => (SBC, 2, 2, Immediate),
+    0xE5 => (SBC, 2, 3, ZeroPage),
+    0xF5 => (SBC, 2, 4, ZeroPageX),
+    0xED => (SBC, 3, 4, Absolute),
+    0xFD => (SBC, 3, 4, AbsoluteX),
+    0xF9 => (SBC, 3, 4, AbsoluteY),
+    0xE1 => (SBC, 2, 6, IndirectX),
+    0xF1 => (SBC, 2, 5, IndirectY),
 
     // Flag Setters
-    0x38u8 => OpCode::new("SEC", 1, 2, AddressingMode::Implied),
-    0xF8u8 => OpCode::new("SED", 1, 2, AddressingMode::Implied),
-    0x78u8 => OpCode::new("SEI", 1, 2, AddressingMode::Implied),
+    0x38 => (SEC, 1, 2, Implied),
+    0xF8 => (SED, 1, 2, Implied),
+    0x78 => (SEI, 1, 2, Implied),
  
     // STA
-    0x85u8 => OpCode::new("STA", 2, 3, AddressingMode::ZeroPage),
-    0x95u8 => OpCode::new("STA", 2, 4, AddressingMode::ZeroPageX),
-    0x8Du8 => OpCode::new("STA", 3, 4, AddressingMode::Absolute),
-    0x9Du8 => OpCode::new("STA", 3, 5, AddressingMode::AbsoluteX),
-    0x99u8 => OpCode::new("STA", 3, 5, AddressingMode::AbsoluteY),
-    0x81u8 => OpCode::new("STA", 2, 6, AddressingMode::IndirectX),
-    0x91u8 => OpCode::new("STA", 2, 6, AddressingMode::IndirectY),
+    0x85 => (STA, 2, 3, ZeroPage),
+    0x95 => (STA, 2, 4, ZeroPageX),
+    0x8D => (STA, 3, 4, Absolute),
+    0x9D => (STA, 3, 5, AbsoluteX),
+    0x99 => (STA, 3, 5, AbsoluteY),
+    0x81 => (STA, 2, 6, IndirectX),
+    0x91 => (STA, 2, 6, IndirectY),
 
     // STX
-    0x86u8 => OpCode::new("STX", 2, 3, AddressingMode::ZeroPage),
-    0x96u8 => OpCode::new("STX", 2, 4, AddressingMode::ZeroPageY),
-    0x8Eu8 => OpCode::new("STX", 3, 4, AddressingMode::Absolute),
+    0x86 => (STX, 2, 3, ZeroPage),
+    0x96 => (STX, 2, 4, ZeroPageY),
+    0x8E => (STX, 3, 4, Absolute),
 
     // STY
-    0x84u8 => OpCode::new("STY", 2, 3, AddressingMode::ZeroPage),
-    0x94u8 => OpCode::new("STY", 2, 4, AddressingMode::ZeroPageX),
-    0x8Cu8 => OpCode::new("STY", 3, 4, AddressingMode::Absolute),
+    0x84 => (STY, 2, 3, ZeroPage),
+    0x94 => (STY, 2, 4, ZeroPageX),
+    0x8C => (STY, 3, 4, Absolute),
 
     // TAX
-    0xAAu8 => OpCode::new("TAX", 1, 2, AddressingMode::Implied),
+    0xAA => (TAX, 1, 2, Implied),
 
     // TAY
-    0xA8u8 => OpCode::new("TAY", 1, 2, AddressingMode::Implied),
+    0xA8 => (TAY, 1, 2, Implied),
 
     // TSX
-    0xBAu8 => OpCode::new("TSX", 1, 2, AddressingMode::Implied),
+    0xBA => (TSX, 1, 2, Implied),
 
     // TXA
-    0x8Au8 => OpCode::new("TXA", 1, 2, AddressingMode::Implied),
+    0x8A => (TXA, 1, 2, Implied),
 
     // TXS
-    0x9au8 => OpCode::new("TXS", 1, 2, AddressingMode::Implied),
+    0x9A => (TXS, 1, 2, Implied),
 
     // TYA
-    0x98u8 => OpCode::new("TYA", 1, 2, AddressingMode::Implied),
+    0x98 => (TYA, 1, 2, Implied),
 
     // BRK
-    0x00u8 => OpCode::new("BRK", 1, 7, AddressingMode::Implied),
+    0x00 => (BRK, 1, 7, Implied),
 
     // Unofficial instructions
     // https://www.nesdev.org/wiki/Programming_with_unofficial_opcodes
 
     // NOP
-    0x1Au8 => OpCode::new("NOP", 1, 2, AddressingMode::Implied),
-    0x3Au8 => OpCode::new("NOP", 1, 2, AddressingMode::Implied),
-    0x5Au8 => OpCode::new("NOP", 1, 2, AddressingMode::Implied),
-    0x7Au8 => OpCode::new("NOP", 1, 2, AddressingMode::Implied),
-    0xDAu8 => OpCode::new("NOP", 1, 2, AddressingMode::Implied),
-    0xFAu8 => OpCode::new("NOP", 1, 2, AddressingMode::Implied),
+    0x1A => (NOP, 1, 2, Implied),
+    0x3A => (NOP, 1, 2, Implied),
+    0x5A => (NOP, 1, 2, Implied),
+    0x7A => (NOP, 1, 2, Implied),
+    0xDA => (NOP, 1, 2, Implied),
+    0xFA => (NOP, 1, 2, Implied),
 
     // IGN
-    0x0Cu8 => OpCode::new("IGN", 3, 4, AddressingMode::Absolute),
-    0x1Cu8 => OpCode::new("IGN", 3, 4, AddressingMode::AbsoluteX),
-    0x3Cu8 => OpCode::new("IGN", 3, 4, AddressingMode::AbsoluteX),
-    0x5Cu8 => OpCode::new("IGN", 3, 4, AddressingMode::AbsoluteX),
-    0x7Cu8 => OpCode::new("IGN", 3, 4, AddressingMode::AbsoluteX),
-    0xDCu8 => OpCode::new("IGN", 3, 4, AddressingMode::AbsoluteX),
-    0xFCu8 => OpCode::new("IGN", 3, 4, AddressingMode::AbsoluteX),
-    0x04u8 => OpCode::new("IGN", 2, 3, AddressingMode::ZeroPage),
-    0x44u8 => OpCode::new("IGN", 2, 3, AddressingMode::ZeroPage),
-    0x64u8 => OpCode::new("IGN", 2, 3, AddressingMode::ZeroPage),
-    0x14u8 => OpCode::new("IGN", 2, 4, AddressingMode::ZeroPageX),
-    0x34u8 => OpCode::new("IGN", 2, 4, AddressingMode::ZeroPageX),
-    0x54u8 => OpCode::new("IGN", 2, 4, AddressingMode::ZeroPageX),
-    0x74u8 => OpCode::new("IGN", 2, 4, AddressingMode::ZeroPageX),
-    0xD4u8 => OpCode::new("IGN", 2, 4, AddressingMode::ZeroPageX),
-    0xF4u8 => OpCode::new("IGN", 2, 4, AddressingMode::ZeroPageX),
+    0x0C => (IGN, 3, 4, Absolute),
+    0x1C => (IGN, 3, 4, AbsoluteX),
+    0x3C => (IGN, 3, 4, AbsoluteX),
+    0x5C => (IGN, 3, 4, AbsoluteX),
+    0x7C => (IGN, 3, 4, AbsoluteX),
+    0xDC => (IGN, 3, 4, AbsoluteX),
+    0xFC => (IGN, 3, 4, AbsoluteX),
+    0x04 => (IGN, 2, 3, ZeroPage),
+    0x44 => (IGN, 2, 3, ZeroPage),
+    0x64 => (IGN, 2, 3, ZeroPage),
+    0x14 => (IGN, 2, 4, ZeroPageX),
+    0x34 => (IGN, 2, 4, ZeroPageX),
+    0x54 => (IGN, 2, 4, ZeroPageX),
+    0x74 => (IGN, 2, 4, ZeroPageX),
+    0xD4 => (IGN, 2, 4, ZeroPageX),
+    0xF4 => (IGN, 2, 4, ZeroPageX),
 
     // SKB
-    0x80u8 => OpCode::new("SKB", 2, 2, AddressingMode::Immediate),
-    0x82u8 => OpCode::new("SKB", 2, 2, AddressingMode::Immediate),
-    0x89u8 => OpCode::new("SKB", 2, 2, AddressingMode::Immediate),
-    0xC2u8 => OpCode::new("SKB", 2, 2, AddressingMode::Immediate),
-    0xE2u8 => OpCode::new("SKB", 2, 2, AddressingMode::Immediate),
+    0x80 => (SKB, 2, 2, Immediate),
+    0x82 => (SKB, 2, 2, Immediate),
+    0x89 => (SKB, 2, 2, Immediate),
+    0xC2 => (SKB, 2, 2, Immediate),
+    0xE2 => (SKB, 2, 2, Immediate),
 
     // LAX Loads addressed value into A then transfer into X
-    0xA3u8 => OpCode::new("LAX", 2, 6, AddressingMode::IndirectX),
-    0xA7u8 => OpCode::new("LAX", 2, 3, AddressingMode::ZeroPage),
-    0xAFu8 => OpCode::new("LAX", 3, 4, AddressingMode::Absolute),
-    0xB3u8 => OpCode::new("LAX", 2, 6, AddressingMode::IndirectY),
-    0xB7u8 => OpCode::new("LAX", 2, 4, AddressingMode::ZeroPageY),
-    0xBFu8 => OpCode::new("LAX", 3, 4, AddressingMode::AbsoluteY),
+    0xA3 => (LAX, 2, 6, IndirectX),
+    0xA7 => (LAX, 2, 3, ZeroPage),
+    0xAF => (LAX, 3, 4, Absolute),
+    0xB3 => (LAX, 2, 5, IndirectY),
+    0xB7 => (LAX, 2, 4, ZeroPageY),
+    0xBF => (LAX, 3, 4, AbsoluteY),
 
     // SAX Stores to memory the result of A & X
-    0x83u8 => OpCode::new("SAX", 2, 6, AddressingMode::IndirectX),
-    0x87u8 => OpCode::new("SAX", 2, 3, AddressingMode::ZeroPage),
-    0x8Fu8 => OpCode::new("SAX", 3, 4, AddressingMode::Absolute),
-    0x97u8 => OpCode::new("SAX", 2, 4, AddressingMode::ZeroPageY),
+    0x83 => (SAX, 2, 6, IndirectX),
+    0x87 => (SAX, 2, 3, ZeroPage),
+    0x8F => (SAX, 3, 4, Absolute),
+    0x97 => (SAX, 2, 4, ZeroPageY),
 
     // SBC Just a copy of SBC Immediate
-    0xEBu8 => OpCode::new("SBC", 2, 2, AddressingMode::Immediate),
+    0xEB => (SBC, 2, 2, Immediate),
 
     // DCP Decrement memory, compare to accumulator
-    0xC3u8 => OpCode::new("DCP", 2, 8, AddressingMode::IndirectX),
-    0xC7u8 => OpCode::new("DCP", 2, 5, AddressingMode::ZeroPage),
-    0xCFu8 => OpCode::new("DCP", 3, 6, AddressingMode::Absolute),
-    0xD3u8 => OpCode::new("DCP", 2, 8, AddressingMode::IndirectY),
-    0xD7u8 => OpCode::new("DCP", 2, 6, AddressingMode::ZeroPageX),
-    0xDBu8 => OpCode::new("DCP", 3, 7, AddressingMode::AbsoluteY),
-    0xDFu8 => OpCode::new("DCP", 3, 7, AddressingMode::AbsoluteX),
+    0xC3 => (DCP, 2, 8, IndirectX),
+    0xC7 => (DCP, 2, 5, ZeroPage),
+    0xCF => (DCP, 3, 6, Absolute),
+    0xD3 => (DCP, 2, 8, IndirectY),
+    0xD7 => (DCP, 2, 6, ZeroPageX),
+    0xDB => (DCP, 3, 7, AbsoluteY),
+    0xDF => (DCP, 3, 7, AbsoluteX),
 
     // ISC Increment memory, subtract from accumulator
-    0xE3u8 => OpCode::new("ISC", 2, 8, AddressingMode::IndirectX),
-    0xE7u8 => OpCode::new("ISC", 2, 5, AddressingMode::ZeroPage),
-    0xEFu8 => OpCode::new("ISC", 3, 6, AddressingMode::Absolute),
-    0xF3u8 => OpCode::new("ISC", 2, 8, AddressingMode::IndirectY),
-    0xF7u8 => OpCode::new("ISC", 2, 6, AddressingMode::ZeroPageX),
-    0xFBu8 => OpCode::new("ISC", 3, 7, AddressingMode::AbsoluteY),
-    0xFFu8 => OpCode::new("ISC", 3, 7, AddressingMode::AbsoluteX),
+    0xE3 => (ISC, 2, 8, IndirectX),
+    0xE7 => (ISC, 2, 5, ZeroPage),
+    0xEF => (ISC, 3, 6, Absolute),
+    0xF3 => (ISC, 2, 8, IndirectY),
+    0xF7 => (ISC, 2, 6, ZeroPageX),
+    0xFB => (ISC, 3, 7, AbsoluteY),
+    0xFF => (ISC, 3, 7, AbsoluteX),
 
     // SLO Shift left memory, OR with A
-    0x03u8 => OpCode::new("SLO", 2, 8, AddressingMode::IndirectX),
-    0x07u8 => OpCode::new("SLO", 2, 5, AddressingMode::ZeroPage),
-    0x0Fu8 => OpCode::new("SLO", 3, 6, AddressingMode::Absolute),
-    0x13u8 => OpCode::new("SLO", 2, 8, AddressingMode::IndirectY),
-    0x17u8 => OpCode::new("SLO", 2, 6, AddressingMode::ZeroPageX),
-    0x1Bu8 => OpCode::new("SLO", 3, 7, AddressingMode::AbsoluteY),
-    0x1Fu8 => OpCode::new("SLO", 3, 7, AddressingMode::AbsoluteX),
+    0x03 => (SLO, 2, 8, IndirectX),
+    0x07 => (SLO, 2, 5, ZeroPage),
+    0x0F => (SLO, 3, 6, Absolute),
+    0x13 => (SLO, 2, 8, IndirectY),
+    0x17 => (SLO, 2, 6, ZeroPageX),
+    0x1B => (SLO, 3, 7, AbsoluteY),
+    0x1F => (SLO, 3, 7, AbsoluteX),
 
     // RLA Rotate left memory, AND with A
-    0x23u8 => OpCode::new("RLA", 2, 8, AddressingMode::IndirectX),
-    0x27u8 => OpCode::new("RLA", 2, 5, AddressingMode::ZeroPage),
-    0x2Fu8 => OpCode::new("RLA", 3, 6, AddressingMode::Absolute),
-    0x33u8 => OpCode::new("RLA", 2, 8, AddressingMode::IndirectY),
-    0x37u8 => OpCode::new("RLA", 2, 6, AddressingMode::ZeroPageX),
-    0x3Bu8 => OpCode::new("RLA", 3, 7, AddressingMode::AbsoluteY),
-    0x3Fu8 => OpCode::new("RLA", 3, 7, AddressingMode::AbsoluteX),
+    0x23 => (RLA, 2, 8, IndirectX),
+    0x27 => (RLA, 2, 5, ZeroPage),
+    0x2F => (RLA, 3, 6, Absolute),
+    0x33 => (RLA, 2, 8, IndirectY),
+    0x37 => (RLA, 2, 6, ZeroPageX),
+    0x3B => (RLA, 3, 7, AbsoluteY),
+    0x3F => (RLA, 3, 7, AbsoluteX),
 
     // SRE Shift right memory, exclusive or with a
-    0x43u8 => OpCode::new("SRE", 2, 8, AddressingMode::IndirectX),
-    0x47u8 => OpCode::new("SRE", 2, 5, AddressingMode::ZeroPage),
-    0x4Fu8 => OpCode::new("SRE", 3, 6, AddressingMode::Absolute),
-    0x53u8 => OpCode::new("SRE", 2, 8, AddressingMode::IndirectY),
-    0x57u8 => OpCode::new("SRE", 2, 6, AddressingMode::ZeroPageX),
-    0x5Bu8 => OpCode::new("SRE", 3, 7, AddressingMode::AbsoluteY),
-    0x5Fu8 => OpCode::new("SRE", 3, 7, AddressingMode::AbsoluteX),
+    0x43 => (SRE, 2, 8, IndirectX),
+    0x47 => (SRE, 2, 5, ZeroPage),
+    0x4F => (SRE, 3, 6, Absolute),
+    0x53 => (SRE, 2, 8, IndirectY),
+    0x57 => (SRE, 2, 6, ZeroPageX),
+    0x5B => (SRE, 3, 7, AbsoluteY),
+    0x5F => (SRE, 3, 7, AbsoluteX),
 
     // RRA Rotate right memory, add with carry with a
-    0x63u8 => OpCode::new("RRA", 2, 8, AddressingMode::IndirectX),
-    0x67u8 => OpCode::new("RRA", 2, 5, AddressingMode::ZeroPage),
-    0x6Fu8 => OpCode::new("RRA", 3, 6, AddressingMode::Absolute),
-    0x73u8 => OpCode::new("RRA", 2, 8, AddressingMode::IndirectY),
-    0x77u8 => OpCode::new("RRA", 2, 6, AddressingMode::ZeroPageX),
-    0x7Bu8 => OpCode::new("RRA", 3, 7, AddressingMode::AbsoluteY),
-    0x7Fu8 => OpCode::new("RRA", 3, 7, AddressingMode::AbsoluteX),
-};
\ No newline at end of file
+    0x63 => (RRA, 2, 8, IndirectX),
+    0x67 => (RRA, 2, 5, ZeroPage),
+    0x6F => (RRA, 3, 6, Absolute),
+    0x73 => (RRA, 2, 8, IndirectY),
+    0x77 => (RRA, 2, 6, ZeroPageX),
+    0x7B => (RRA, 3, 7, AbsoluteY),
+    0x7F => (RRA, 3, 7, AbsoluteX),
+
+    // ANC AND with A, then copy the negative flag into carry
+    0x0B => (ANC, 2, 2, Immediate),
+    0x2B => (ANC, 2, 2, Immediate),
+
+    // ALR AND with A, then shift A right
+    0x4B => (ALR, 2, 2, Immediate),
+
+    // ARR AND with A, then rotate A right. Carry and overflow come from bits 6 and 5 of the result
+    0x6B => (ARR, 2, 2, Immediate),
+
+    // AXS Store A & X minus the operand into X, without borrow
+    0xCB => (AXS, 2, 2, Immediate),
+
+    // LAS AND memory with the stack pointer, then store into A, X and the stack pointer
+    0xBB => (LAS, 3, 4, AbsoluteY),
+
+    // XAA and LXA are unstable on real hardware, see transfer_x_and_a and load_a_and_x_immediate
+    0x8B => (XAA, 2, 2, Immediate),
+    0xAB => (LXA, 2, 2, Immediate),
+
+    // SHX, SHY, TAS and AHX store a register ANDed with the high byte of the address plus one
+    0x9E => (SHX, 3, 5, AbsoluteY),
+    0x9C => (SHY, 3, 5, AbsoluteX),
+    0x9B => (TAS, 3, 5, AbsoluteY),
+    0x93 => (AHX, 2, 6, IndirectY),
+    0x9F => (AHX, 3, 5, AbsoluteY),
+
+    // JAM Locks up the CPU until it is reset. Test programs use this to stop execution since BRK is a real interrupt
+    0x02 => (JAM, 1, 2, Implied),
+    0x12 => (JAM, 1, 2, Implied),
+    0x22 => (JAM, 1, 2, Implied),
+    0x32 => (JAM, 1, 2, Implied),
+    0x42 => (JAM, 1, 2, Implied),
+    0x52 => (JAM, 1, 2, Implied),
+    0x62 => (JAM, 1, 2, Implied),
+    0x72 => (JAM, 1, 2, Implied),
+    0x92 => (JAM, 1, 2, Implied),
+    0xB2 => (JAM, 1, 2, Implied),
+    0xD2 => (JAM, 1, 2, Implied),
+    0xF2 => (JAM, 1, 2, Implied),
+};
+
+#[cfg(test)]
+mod op_code_tests {
+    use super::*;
+
+    #[test]
+    pub fn decode_table() {
+        let lda = decode(0xBD).unwrap();
+        assert_eq!(lda.mnemonic, Mnemonic::LDA);
+        assert_eq!(lda.addressing_mode, AddressingMode::AbsoluteX);
+        assert_eq!(lda.bytes, 3);
+        assert_eq!(lda.cycles, 4);
+        assert!(lda.page_cross_penalty);
+
+        let sta = decode(0x9D).unwrap();
+        assert_eq!(sta.mnemonic, Mnemonic::STA);
+        assert!(!sta.page_cross_penalty);
+    }
+
+    #[test]
+    pub fn every_op_code_is_decoded() {
+        for op_code in 0..=0xFFu8 {
+            assert!(decode(op_code).is_some(), "{op_code:#04x} is missing from the table");
+        }
+    }
+}
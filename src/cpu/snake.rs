@@ -29,6 +29,6 @@ pub fn snake_program() -> Vec<u8> {
 impl CPU {
     pub fn load_snake(&mut self) {
         let snake_rom = Rom::from_rom("./snake.nes").unwrap();
-        self.load_rom(snake_rom);
+        self.load_rom(snake_rom).unwrap();
     }
 }
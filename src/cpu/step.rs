@@ -0,0 +1,233 @@
+use super::addressing_modes::AddressingMode;
+use super::interrupts::{Interrupt, INTERRUPT_CYCLES};
+use super::opcodes::{decode, Mnemonic};
+use super::CPU;
+use crate::{error::NesError, MemAccess};
+
+const OAM_DMA_CYCLES: usize = 513;
+
+// Describes what a single call to CPU::step did
+#[derive(Debug)]
+pub struct StepResult {
+    pub op_code: u8,
+    pub mnemonic: Option<Mnemonic>,
+    pub addressing_mode: AddressingMode,
+    // The address the instruction operated on, or the target of a jump or taken branch
+    pub effective_address: Option<u16>,
+    // Includes page-cross and branch penalties, OAM DMA stalls and any interrupt serviced after the instruction
+    pub cycles: usize,
+    pub branched: bool,
+    pub interrupt: Option<Interrupt>,
+    pub halted: bool,
+    pub invalid_op_code: bool,
+}
+
+impl CPU {
+    // Executes one instruction then services any interrupt that became pending while it ran.
+    // Returns the first error raised under the Stop policy once the instruction has finished.
+    pub fn step(&mut self) -> Result<StepResult, NesError> {
+        let start_cycles = self.cycles;
+        let op_code = match self.halted {
+            true => 0x02,
+            false => self.mem_read(self.program_counter),
+        };
+        let op_code_params = match decode(op_code) {
+            Some(op_code_params) => op_code_params,
+            None => {
+                self.bus.report(NesError::InvalidOpCode { op_code, addr: self.program_counter });
+                self.program_counter = self.program_counter.wrapping_add(1);
+                if let Some(error) = self.bus.take_error() {
+                    return Err(error);
+                }
+                return Ok(StepResult {
+                    op_code,
+                    mnemonic: None,
+                    addressing_mode: AddressingMode::Implied,
+                    effective_address: None,
+                    cycles: 0,
+                    branched: false,
+                    interrupt: None,
+                    halted: self.halted,
+                    invalid_op_code: true,
+                });
+            }
+        };
+
+        // A jammed CPU stays stuck on its JAM until it is reset
+        if self.halted {
+            return Ok(StepResult {
+                op_code,
+                mnemonic: Some(op_code_params.mnemonic),
+                addressing_mode: op_code_params.addressing_mode.clone(),
+                effective_address: None,
+                cycles: 0,
+                branched: false,
+                interrupt: None,
+                halted: true,
+                invalid_op_code: false,
+            });
+        }
+
+        self.program_counter += 1;
+        self.page_crossed = false;
+        self.operand_address = None;
+        // Taken branches add their own extra cycles, see branch()
+        self.cycles += op_code_params.cycles as usize;
+
+        // Branches and jumps move the program counter themselves
+        let jumped = (op_code_params.handler)(self, &op_code_params.addressing_mode);
+        let branched = jumped && op_code_params.mnemonic.is_branch();
+
+        if self.page_crossed && op_code_params.page_cross_penalty {
+            self.cycles += 1;
+        }
+        if !jumped {
+            self.program_counter += op_code_params.bytes - 1;
+        }
+
+        // OAM DMA halts the CPU for 512 cycles of copying plus one to get going, and one more to line up
+        // with a read cycle when it starts on an odd cycle
+        if self.bus.take_dma() {
+            self.cycles += OAM_DMA_CYCLES + self.cycles % 2;
+        }
+
+        self.bus.tick(self.cycles - start_cycles);
+        if let Some(error) = self.bus.take_error() {
+            return Err(error);
+        }
+
+        let interrupt = match self.halted {
+            true => None,
+            false => self.poll_interrupts(),
+        };
+        if interrupt.is_some() {
+            self.bus.tick(INTERRUPT_CYCLES);
+        }
+
+        Ok(StepResult {
+            op_code,
+            mnemonic: Some(op_code_params.mnemonic),
+            addressing_mode: op_code_params.addressing_mode.clone(),
+            effective_address: self.operand_address,
+            cycles: self.cycles - start_cycles,
+            branched,
+            interrupt,
+            halted: self.halted,
+            invalid_op_code: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod step_tests {
+    use super::*;
+
+    #[test]
+    pub fn step_reports_executed_instruction() {
+        let mut cpu = CPU::new();
+        cpu.program_counter = 0x8000;
+        cpu.register_x = 0x01;
+        cpu.load(vec!(0xBD, 0xFF, 0x00, 0x02));
+
+        let result = cpu.step().unwrap();
+        assert_eq!(result.op_code, 0xBD);
+        assert_eq!(result.mnemonic, Some(Mnemonic::LDA));
+        assert_eq!(result.addressing_mode, AddressingMode::AbsoluteX);
+        assert_eq!(result.effective_address, Some(0x0100));
+        assert_eq!(result.cycles, 5);
+        assert!(!result.branched);
+        assert!(!result.halted);
+        assert_eq!(cpu.program_counter, 0x8003);
+
+        let result = cpu.step().unwrap();
+        assert_eq!(result.mnemonic, Some(Mnemonic::JAM));
+        assert!(result.halted);
+        assert_eq!(cpu.program_counter, 0x8004);
+
+        // Stepping a halted cpu does nothing
+        let result = cpu.step().unwrap();
+        assert!(result.halted);
+        assert_eq!(result.cycles, 0);
+        assert_eq!(cpu.program_counter, 0x8004);
+    }
+
+    #[test]
+    pub fn step_reports_taken_branch() {
+        let mut cpu = CPU::new();
+        cpu.program_counter = 0x8000;
+        cpu.load(vec!(0xD0, 0x02, 0xEA, 0xEA, 0x02));
+
+        let result = cpu.step().unwrap();
+        assert!(result.branched);
+        assert_eq!(result.effective_address, Some(0x8004));
+        assert_eq!(result.cycles, 3);
+        assert_eq!(cpu.program_counter, 0x8004);
+    }
+
+    #[test]
+    pub fn step_services_pending_interrupt() {
+        let mut cpu = CPU::new();
+        cpu.mem_write_u16(0xFFFA, 0x9000);
+        cpu.program_counter = 0x8000;
+        cpu.load(vec!(0xEA, 0x02));
+        cpu.raise_nmi();
+
+        let result = cpu.step().unwrap();
+        assert_eq!(result.mnemonic, Some(Mnemonic::NOP));
+        assert_eq!(result.interrupt, Some(Interrupt::NMI));
+        assert_eq!(result.cycles, 2 + 7);
+        assert_eq!(cpu.program_counter, 0x9000);
+    }
+
+    #[test]
+    pub fn step_runs_unofficial_op_code() {
+        let mut cpu = CPU::new();
+        cpu.program_counter = 0x8000;
+        cpu.register_a = 0xF0;
+        cpu.register_x = 0x3C;
+        cpu.load(vec!(0xCB, 0x10, 0x02));
+
+        let result = cpu.step().unwrap();
+        assert!(!result.invalid_op_code);
+        assert_eq!(result.mnemonic, Some(Mnemonic::AXS));
+        assert_eq!(result.cycles, 2);
+        assert_eq!(cpu.register_x, 0x20);
+        assert_eq!(cpu.program_counter, 0x8002);
+    }
+
+    #[test]
+    pub fn ppu_vblank_interrupts_cpu() {
+        let mut cpu = CPU::new();
+        cpu.mem_write_u16(0xFFFA, 0x9000);
+        cpu.program_counter = 0x8000;
+        // LDA #$80, STA $2000, JMP $8005
+        cpu.load(vec!(0xA9, 0x80, 0x8D, 0x00, 0x20, 0x4C, 0x05, 0x80));
+
+        let mut result = cpu.step().unwrap();
+        while result.interrupt.is_none() {
+            result = cpu.step().unwrap();
+        }
+        assert_eq!(result.interrupt, Some(Interrupt::NMI));
+        assert_eq!(cpu.program_counter, 0x9000);
+        // The NMI is taken at the first instruction boundary after dot 1 of scanline 241
+        assert!(cpu.cycles - INTERRUPT_CYCLES >= 27394 && cpu.cycles - INTERRUPT_CYCLES < 27394 + 3);
+    }
+
+    #[test]
+    pub fn oam_dma_stalls_cpu() {
+        let mut cpu = CPU::new();
+        cpu.program_counter = 0x8000;
+        // LDA #$02, STA $4014 twice
+        cpu.load(vec!(0xA9, 0x02, 0x8D, 0x14, 0x40, 0x8D, 0x14, 0x40, 0x02));
+
+        cpu.step().unwrap();
+        // The DMA starts on an even cycle
+        let result = cpu.step().unwrap();
+        assert_eq!(result.cycles, 4 + 513);
+        assert_eq!(cpu.cycles, 2 + 4 + 513);
+
+        // Odd cycle, one extra alignment cycle
+        let result = cpu.step().unwrap();
+        assert_eq!(result.cycles, 4 + 514);
+    }
+}
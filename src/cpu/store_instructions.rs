@@ -22,6 +22,40 @@ impl CPU {
         let val = self.register_a & self.register_x;
         self.mem_write(addr, val);
     }
+
+    // SHX, SHY, TAS and AHX AND the stored value with the high byte of the base address plus one.
+    // When indexing crosses a page the carry into the high byte is lost and the stored value replaces it.
+    fn store_anded_with_high_byte(&mut self, mode: &AddressingMode, val: u8) {
+        let addr = self.get_operand_address(mode);
+        let index = match mode {
+            AddressingMode::AbsoluteX => self.register_x,
+            _ => self.register_y,
+        };
+        let base_hi = (addr.wrapping_sub(index as u16) >> 8) as u8;
+        let result = val & base_hi.wrapping_add(1);
+        let addr = match self.page_crossed {
+            true => u16::from_le_bytes([addr as u8, result]),
+            false => addr,
+        };
+        self.mem_write(addr, result);
+    }
+
+    pub fn store_x_high_byte(&mut self, mode: &AddressingMode) {
+        self.store_anded_with_high_byte(mode, self.register_x);
+    }
+
+    pub fn store_y_high_byte(&mut self, mode: &AddressingMode) {
+        self.store_anded_with_high_byte(mode, self.register_y);
+    }
+
+    pub fn store_a_anded_x_high_byte(&mut self, mode: &AddressingMode) {
+        self.store_anded_with_high_byte(mode, self.register_a & self.register_x);
+    }
+
+    pub fn transfer_a_anded_x_to_stack_pointer(&mut self, mode: &AddressingMode) {
+        self.stack_pointer = self.register_a & self.register_x;
+        self.store_anded_with_high_byte(mode, self.stack_pointer);
+    }
 }
 
 #[cfg(test)]
@@ -67,4 +101,54 @@ mod store_test {
         cpu.store_a_anded_x(&AddressingMode::Absolute);
         assert_eq!(cpu.mem_read(0x1A1A), cpu.register_a & cpu.register_x);
     }
+
+    #[test]
+    fn shx_absolute_y() {
+        let mut cpu = CPU::new();
+        cpu.register_x = 0xFF;
+        cpu.register_y = 0x05;
+        cpu.program_counter = 0x8000;
+        cpu.mem_write_u16(0x8000, 0x0120);
+        cpu.store_x_high_byte(&AddressingMode::AbsoluteY);
+        assert_eq!(cpu.mem_read(0x0125), 0x02);
+    }
+
+    #[test]
+    fn shy_page_cross_replaces_high_byte() {
+        let mut cpu = CPU::new();
+        cpu.register_x = 0x20;
+        cpu.register_y = 0x01;
+        cpu.program_counter = 0x8000;
+        cpu.mem_write_u16(0x8000, 0x01F0);
+        cpu.mem_write(0x0010, 0xAA);
+        cpu.store_y_high_byte(&AddressingMode::AbsoluteX);
+        // 0x01 & (0x01 + 1) = 0, which also becomes the high byte of 0x0210
+        assert_eq!(cpu.mem_read(0x0010), 0x00);
+    }
+
+    #[test]
+    fn tas_absolute_y() {
+        let mut cpu = CPU::new();
+        cpu.register_a = 0xFF;
+        cpu.register_x = 0x0F;
+        cpu.register_y = 0x01;
+        cpu.program_counter = 0x8000;
+        cpu.mem_write_u16(0x8000, 0x0700);
+        cpu.transfer_a_anded_x_to_stack_pointer(&AddressingMode::AbsoluteY);
+        assert_eq!(cpu.stack_pointer, 0x0F);
+        assert_eq!(cpu.mem_read(0x0701), 0x08);
+    }
+
+    #[test]
+    fn ahx_indirect_y() {
+        let mut cpu = CPU::new();
+        cpu.register_a = 0xFF;
+        cpu.register_x = 0xF3;
+        cpu.register_y = 0x01;
+        cpu.program_counter = 0x8000;
+        cpu.mem_write(0x8000, 0x10);
+        cpu.mem_write_u16(0x10, 0x0600);
+        cpu.store_a_anded_x_high_byte(&AddressingMode::IndirectY);
+        assert_eq!(cpu.mem_read(0x0601), 0x07 & 0xF3);
+    }
 }
@@ -0,0 +1,184 @@
+use std::{fmt, io};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum NesError {
+    WriteOnlyRegisterRead { addr: u16 },
+    ReadOnlyRegisterWrite { addr: u16, data: u8 },
+    RomWrite { addr: u16, data: u8 },
+    InvalidOpCode { op_code: u8, addr: u16 },
+    UnmappedAccess { addr: u16 },
+}
+
+// Errors are grouped into classes so that each class can be handled with its own policy
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorClass {
+    RegisterAccess,
+    RomWrite,
+    InvalidOpCode,
+    UnmappedAccess,
+}
+
+const ERROR_CLASS_COUNT: usize = 4;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorPolicy {
+    Panic,
+    // Print the error and carry on as if the access was ignored
+    Log,
+    // Record the error, the current instruction finishes and CPU::step returns it
+    Stop,
+}
+
+impl NesError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            NesError::WriteOnlyRegisterRead { .. } | NesError::ReadOnlyRegisterWrite { .. } => ErrorClass::RegisterAccess,
+            NesError::RomWrite { .. } => ErrorClass::RomWrite,
+            NesError::InvalidOpCode { .. } => ErrorClass::InvalidOpCode,
+            NesError::UnmappedAccess { .. } => ErrorClass::UnmappedAccess,
+        }
+    }
+}
+
+impl fmt::Display for NesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NesError::WriteOnlyRegisterRead { addr } => write!(f, "Attempt to read from write-only register {addr:04X}"),
+            NesError::ReadOnlyRegisterWrite { addr, data } => {
+                write!(f, "Attempt to write {data:02X} to read-only register {addr:04X}")
+            },
+            NesError::RomWrite { addr, data } => write!(f, "Attempt to write {data:02X} to cartridge ROM at {addr:04X}"),
+            NesError::InvalidOpCode { op_code, addr } => write!(f, "{op_code:02X} at {addr:04X} is not a valid operation"),
+            NesError::UnmappedAccess { addr } => write!(f, "Invalid RAM access at {addr:04X}"),
+        }
+    }
+}
+
+impl std::error::Error for NesError {}
+
+// Reasons a rom file can be rejected while loading it
+#[derive(Debug)]
+pub enum RomError {
+    Io(io::Error),
+    BadMagic,
+    TruncatedHeader { len: usize },
+    TruncatedTrainer,
+    TruncatedPrgRom { expected: usize, actual: usize },
+    TruncatedChrRom { expected: usize, actual: usize },
+    EmptyPrgRom,
+    // A NES 2.0 exponent-multiplier size too big to address
+    InvalidRomSize { lsb: u8, msb: u8 },
+    // Bytes past the end of CHR ROM that the header doesn't account for
+    TrailingData { len: usize },
+    UnsupportedMapper { mapper: u16, submapper: u8 },
+    // PRG ROM that isn't whole banks, or too small for the board's fixed banks
+    InvalidPrgSize { mapper: u16, len: usize },
+}
+
+impl fmt::Display for RomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RomError::Io(error) => write!(f, "Could not read rom: {error}"),
+            RomError::BadMagic => write!(f, "File is not in the iNES file format"),
+            RomError::TruncatedHeader { len } => write!(f, "Header is {len} bytes, expected 16"),
+            RomError::TruncatedTrainer => write!(f, "File ends inside the trainer"),
+            RomError::TruncatedPrgRom { expected, actual } => {
+                write!(f, "PRG ROM is {actual} bytes, the header says {expected}")
+            },
+            RomError::TruncatedChrRom { expected, actual } => {
+                write!(f, "CHR ROM is {actual} bytes, the header says {expected}")
+            },
+            RomError::EmptyPrgRom => write!(f, "Header declares no PRG ROM"),
+            RomError::InvalidRomSize { lsb, msb } => {
+                write!(f, "Header declares an impossible ROM size (size byte {lsb:#04X}, MSB nibble {msb:#X})")
+            },
+            RomError::TrailingData { len } => write!(f, "{len} unexpected bytes after CHR ROM"),
+            RomError::InvalidPrgSize { mapper, len } => write!(f, "{len} bytes of PRG ROM don't fit mapper {mapper}"),
+            RomError::UnsupportedMapper { mapper, submapper: 0 } => write!(f, "Mapper {mapper} is not supported"),
+            RomError::UnsupportedMapper { mapper, submapper } => {
+                write!(f, "Mapper {mapper} submapper {submapper} is not supported")
+            },
+        }
+    }
+}
+
+impl std::error::Error for RomError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RomError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RomError {
+    fn from(error: io::Error) -> Self {
+        RomError::Io(error)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ErrorPolicies([ErrorPolicy; ERROR_CLASS_COUNT]);
+
+impl Default for ErrorPolicies {
+    // Unmapped accesses have always been logged, everything else used to abort
+    fn default() -> Self {
+        let mut policies = ErrorPolicies([ErrorPolicy::Panic; ERROR_CLASS_COUNT]);
+        policies.set(ErrorClass::UnmappedAccess, ErrorPolicy::Log);
+        policies
+    }
+}
+
+impl ErrorPolicies {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Applies the same policy to every class, handy for test harnesses that want every error back
+    pub fn all(policy: ErrorPolicy) -> Self {
+        ErrorPolicies([policy; ERROR_CLASS_COUNT])
+    }
+
+    pub fn get(&self, class: ErrorClass) -> ErrorPolicy {
+        self.0[class as usize]
+    }
+
+    pub fn set(&mut self, class: ErrorClass, policy: ErrorPolicy) {
+        self.0[class as usize] = policy;
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    pub fn default_policies() {
+        let policies = ErrorPolicies::new();
+        assert_eq!(policies.get(ErrorClass::RomWrite), ErrorPolicy::Panic);
+        assert_eq!(policies.get(ErrorClass::UnmappedAccess), ErrorPolicy::Log);
+    }
+
+    #[test]
+    pub fn set_policy() {
+        let mut policies = ErrorPolicies::all(ErrorPolicy::Stop);
+        policies.set(ErrorClass::InvalidOpCode, ErrorPolicy::Log);
+        assert_eq!(policies.get(ErrorClass::InvalidOpCode), ErrorPolicy::Log);
+        assert_eq!(policies.get(ErrorClass::RomWrite), ErrorPolicy::Stop);
+    }
+
+    #[test]
+    pub fn rom_error_messages() {
+        assert_eq!(RomError::UnsupportedMapper { mapper: 255, submapper: 0 }.to_string(), "Mapper 255 is not supported");
+        assert_eq!(
+            RomError::TruncatedPrgRom { expected: 0x4000, actual: 10 }.to_string(),
+            "PRG ROM is 10 bytes, the header says 16384"
+        );
+    }
+
+    #[test]
+    pub fn error_class() {
+        assert_eq!(NesError::ReadOnlyRegisterWrite { addr: 0x2002, data: 0 }.class(), ErrorClass::RegisterAccess);
+        assert_eq!(NesError::InvalidOpCode { op_code: 0x02, addr: 0x8000 }.class(), ErrorClass::InvalidOpCode);
+    }
+}
@@ -1,9 +1,9 @@
-use crate::{cpu::{addressing_modes::AddressingMode, opcodes::OP_CODE_REF_TABLE, CPU}, MemAccess};
+use crate::{cpu::{addressing_modes::AddressingMode, opcodes::decode, CPU}, MemAccess};
 
 pub fn trace(cpu: &mut CPU) -> String {
     let op_code_byte = cpu.mem_read(cpu.program_counter);
-    let op_code = OP_CODE_REF_TABLE.get(&op_code_byte)
-        .expect(&format!("{op_code_byte} is not a valid opcode"));
+    let op_code = decode(op_code_byte)
+        .unwrap_or_else(|| panic!("{op_code_byte} is not a valid opcode"));
     let op_code_args = {
         let arg1 = if op_code.bytes > 1 
             { &format!("{:02X}", cpu.mem_read(cpu.program_counter + 1)) }
@@ -22,7 +22,7 @@ pub fn trace(cpu: &mut CPU) -> String {
             _ => false,
         };
 
-        if trimmed_addr.len() == 0 { String::from("       ") }
+        if trimmed_addr.is_empty() { String::from("       ") }
 
         else if is_indirect {
             format!("(${trimmed_addr})")
@@ -58,7 +58,7 @@ pub fn trace(cpu: &mut CPU) -> String {
         cpu.program_counter,
         op_code_byte,
         op_code_args,
-        op_code.instruction,
+        op_code.mnemonic,
         op_code_parametize,
         indirect_addr,
         cpu.register_a,
@@ -88,12 +88,12 @@ mod format_tests {
     fn test_format_trace() {
         let mut bus = Bus::new(
             Rom::from_rom("./nestest.nes").unwrap()
-        );
+        ).unwrap();
         bus.mem_write(100, 0xa2);
         bus.mem_write(101, 0x01);
         bus.mem_write(102, 0xCA);
         bus.mem_write(103, 0x88);
-        bus.mem_write(104, 0x0);
+        bus.mem_write(104, 0x02);
 
         let mut cpu = CPU::new_with_bus(bus);
         cpu.program_counter = 0x64;
@@ -104,7 +104,7 @@ mod format_tests {
         let mut result: Vec<String> = vec!();
         cpu.run_with_callback(|cpu| {
             result.push(trace(cpu))
-        });
+        }).unwrap();
         assert_eq!(
             "0064  A2 01     LDX #$01                        A:01 X:02 Y:03 P:24 SP:FD",
             result[0]
@@ -123,9 +123,10 @@ mod format_tests {
     fn test_format_mem_access() {
         let mut bus = Bus::new(
             Rom::from_rom("nestest.nes").unwrap()
-        );
+        ).unwrap();
         bus.mem_write(100, 0x11);
         bus.mem_write(101, 0x33);
+        bus.mem_write(102, 0x02);
         bus.mem_write(0x33, 00);
         bus.mem_write(0x34, 04);
         bus.mem_write(0x400, 0xAA);
@@ -137,7 +138,7 @@ mod format_tests {
         let mut result: Vec<String> = vec!();
         cpu.run_with_callback(|cpu| {
             result.push(trace(cpu));
-        });
+        }).unwrap();
         assert_eq!(
             "0064  11 33     ORA ($33),Y = 0400 @ 0400 = AA  A:00 X:00 Y:00 P:24 SP:FD",
             result[0]
@@ -3,12 +3,17 @@ pub mod bus;
 pub mod rom;
 pub mod ppu;
 pub mod format_test;
+pub mod error;
+pub mod mapper;
 
-#[derive(PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Mirroring {
     Vertical,
     Horizontal,
     FourScreen,
+    // Mapper controlled, every nametable shows the first or the second page of VRAM
+    SingleScreenLower,
+    SingleScreenUpper,
 }
 
 pub trait MemAccess {
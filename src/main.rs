@@ -1,6 +1,7 @@
+use std::process::ExitCode;
 use std::time::Duration;
 
-use nes_rust::{cpu::{snake, CPU}, format_test::trace, rom::Rom, MemAccess};
+use nes_rust::{bus::save_file, cpu::{snake, CPU}, format_test::trace, rom::Rom, MemAccess};
 use rand::Rng;
 use sdl2::{event::Event, keyboard::Keycode, pixels::{Color, PixelFormatEnum}, EventPump};
 
@@ -41,19 +42,26 @@ use sdl2::{event::Event, keyboard::Keycode, pixels::{Color, PixelFormatEnum}, Ev
 //         }
 
 //         std::thread::sleep(Duration::from_nanos(70_000));
-//     });
+//     }).unwrap();
 // }
 
 // This code block is used for test rom logging
-fn main() {
+fn main() -> ExitCode {
+    let rom_path = "./nestest.nes";
     let mut cpu = CPU::new();
-    cpu.load_rom(Rom::from_rom("./nestest.nes").unwrap());
+    cpu.load_rom(Rom::from_rom(rom_path).unwrap()).unwrap();
+    cpu.attach_save_file(save_file::default_path(rom_path)).unwrap();
     cpu.reset();
     cpu.program_counter = 0xC000;
     cpu.indirect_bug_enabled = true;
-    cpu.run_with_callback(|cpu| {
+    if let Err(error) = cpu.run_with_callback(|cpu| {
         println!("{}", trace(cpu));
-    });
+    }) {
+        eprintln!("{error}");
+        // Returning instead of exiting lets the save file flush when the CPU is dropped
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
 }
 
 fn handle_user_input(cpu: &mut CPU, event_pump: &mut EventPump) {
@@ -0,0 +1,78 @@
+use crate::error::NesError;
+
+// Most boards without CHR ROM carry a single 8KB RAM chip
+pub const DEFAULT_CHR_RAM_SIZE: usize = 0x2000;
+
+// The pattern table memory on the cartridge. Roms without CHR ROM get writable CHR-RAM instead, which the
+// game fills in through PPUDATA.
+pub struct ChrMemory {
+    data: Vec<u8>,
+    is_ram: bool,
+}
+
+impl ChrMemory {
+    pub fn new(chr_rom: Vec<u8>, ram_size: usize) -> Self {
+        match chr_rom.is_empty() {
+            true => ChrMemory { data: vec![0; ram_size.max(1)], is_ram: true },
+            false => ChrMemory { data: chr_rom, is_ram: false },
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn is_ram(&self) -> bool {
+        self.is_ram
+    }
+
+    // Indexes past the end wrap around, like the unused address lines on smaller chips
+    pub fn read(&self, index: usize) -> u8 {
+        self.data[index % self.data.len()]
+    }
+
+    pub fn write(&mut self, index: usize, addr: u16, data: u8) -> Result<(), NesError> {
+        if !self.is_ram {
+            return Err(NesError::RomWrite { addr, data });
+        }
+        let len = self.data.len();
+        self.data[index % len] = data;
+        Ok(())
+    }
+}
+
+// Mappers built from just the CHR ROM get the default sized RAM when there is none
+impl From<Vec<u8>> for ChrMemory {
+    fn from(chr_rom: Vec<u8>) -> Self {
+        ChrMemory::new(chr_rom, DEFAULT_CHR_RAM_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod chr_tests {
+    use super::*;
+
+    #[test]
+    pub fn chr_rom_is_read_only() {
+        let mut chr = ChrMemory::new(vec![1, 2, 3, 4], 0x8000);
+        assert!(!chr.is_ram());
+        assert_eq!(chr.read(5), 2);
+        assert_eq!(chr.write(1, 0x0001, 9), Err(NesError::RomWrite { addr: 0x0001, data: 9 }));
+    }
+
+    #[test]
+    pub fn chr_ram_takes_requested_size() {
+        let mut chr = ChrMemory::new(vec![], 0x8000);
+        assert!(chr.is_ram());
+        assert_eq!(chr.len(), 0x8000);
+        chr.write(0x4001, 0x0001, 9).unwrap();
+        assert_eq!(chr.read(0x4001), 9);
+
+        let chr = ChrMemory::from(vec![]);
+        assert_eq!(chr.len(), DEFAULT_CHR_RAM_SIZE);
+    }
+}
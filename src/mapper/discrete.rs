@@ -0,0 +1,218 @@
+use super::{ChrMemory, Mapper, PrgRam};
+use crate::{error::NesError, Mirroring};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x2000;
+
+// Boards built from a latch and a few logic chips. A single register covers the whole $8000 - $FFFF range.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Board {
+    // Mapper 2. Switchable 16KB bank at $8000, last bank fixed at $C000
+    UxRom,
+    // Mapper 3. Fixed PRG like NROM, switchable 8KB CHR bank
+    CnRom,
+    // Mapper 7. Switchable 32KB PRG bank, BIT 4 picks the single screen nametable
+    AxRom,
+    // Mapper 66. BIT 4-5: 32KB PRG bank; BIT 0-1: 8KB CHR bank
+    GxRom,
+    // Mapper 11. BIT 0-1: 32KB PRG bank; BIT 4-7: 8KB CHR bank
+    ColorDreams,
+}
+
+impl Board {
+    // Boards whose ROM still drives the data bus during writes. AxROM is split, ANROM has no conflicts
+    // while AMROM and AOROM do, so it defaults to the more forgiving behaviour.
+    pub fn has_bus_conflicts(&self) -> bool {
+        *self != Board::AxRom
+    }
+
+    // NES 2.0 submappers of UxROM, CNROM and AxROM settle it per rom: 1 has no bus conflicts, 2 has them.
+    // Anything else keeps the board default.
+    pub fn has_bus_conflicts_for(&self, submapper: u8) -> bool {
+        match (self, submapper) {
+            (Board::UxRom | Board::CnRom | Board::AxRom, 1) => false,
+            (Board::UxRom | Board::CnRom | Board::AxRom, 2) => true,
+            _ => self.has_bus_conflicts(),
+        }
+    }
+}
+
+pub struct Discrete {
+    board: Board,
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    // None of these boards has work RAM, but NES 2.0 headers or a trainer can ask for some at $6000
+    prg_ram: PrgRam,
+    mirroring: Mirroring,
+    // When set, writes are ANDed with the ROM byte at the same address like on the real board
+    pub bus_conflicts: bool,
+
+    prg_bank: usize,
+    chr_bank: usize,
+}
+
+impl Discrete {
+    pub fn new(board: Board, submapper: u8, prg_rom: Vec<u8>, chr: impl Into<ChrMemory>, mirroring: Mirroring) -> Self {
+        Discrete {
+            board,
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: PrgRam::new(0, false),
+            mirroring: match board {
+                Board::AxRom => Mirroring::SingleScreenLower,
+                _ => mirroring,
+            },
+            bus_conflicts: board.has_bus_conflicts_for(submapper),
+            prg_bank: 0,
+            chr_bank: 0,
+        }
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        let offset = (addr - 0x8000) as usize;
+        let index = match self.board {
+            Board::UxRom if offset < PRG_BANK_SIZE => self.prg_bank * PRG_BANK_SIZE + offset,
+            // The fixed bank at $C000 is always the last one
+            Board::UxRom => self.prg_rom.len() - PRG_BANK_SIZE + offset % PRG_BANK_SIZE,
+            Board::CnRom => offset,
+            _ => self.prg_bank * 2 * PRG_BANK_SIZE + offset,
+        };
+        index % self.prg_rom.len()
+    }
+
+    fn write_register(&mut self, data: u8) {
+        let data = data as usize;
+        match self.board {
+            Board::UxRom => self.prg_bank = data,
+            Board::CnRom => self.chr_bank = data,
+            Board::AxRom => {
+                self.prg_bank = data & 0b111;
+                self.mirroring = match data & 0b1_0000 {
+                    0 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            },
+            Board::GxRom => {
+                self.prg_bank = (data >> 4) & 0b11;
+                self.chr_bank = data & 0b11;
+            },
+            Board::ColorDreams => {
+                self.prg_bank = data & 0b11;
+                self.chr_bank = data >> 4;
+            },
+        }
+    }
+}
+
+impl Mapper for Discrete {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => Some(self.prg_ram.read((addr - 0x6000) as usize)),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        match addr {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => {
+                self.prg_ram.write((addr - 0x6000) as usize, data);
+                return Ok(());
+            },
+            0x0000..=0x7FFF => return Err(NesError::UnmappedAccess { addr }),
+            _ => (),
+        }
+        let data = match self.bus_conflicts {
+            true => data & self.prg_rom[self.prg_index(addr)],
+            false => data,
+        };
+        self.write_register(data);
+        Ok(())
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr.read(self.chr_bank * CHR_BANK_SIZE + addr as usize)
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(self.chr_bank * CHR_BANK_SIZE + addr as usize, addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring.clone()
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+#[cfg(test)]
+mod discrete_tests {
+    use super::*;
+
+    // Every 16KB PRG bank and 8KB CHR bank starts with its own bank number, the rest of the ROM is $FF
+    // so bus conflicts don't get in the way
+    fn test_board(board: Board) -> Discrete {
+        let mut prg_rom = vec![0xFF; 16 * PRG_BANK_SIZE];
+        for bank in 0..16 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut chr_rom = vec![0; 16 * CHR_BANK_SIZE];
+        for bank in 0..16 {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Discrete::new(board, 0, prg_rom, chr_rom, Mirroring::Vertical)
+    }
+
+    #[test]
+    pub fn uxrom() {
+        let mut mapper = test_board(Board::UxRom);
+        mapper.cpu_write(0x8001, 5).unwrap();
+        assert_eq!(mapper.cpu_read(0x8000), Some(5));
+        assert_eq!(mapper.cpu_read(0xC000), Some(15));
+    }
+
+    #[test]
+    pub fn cnrom() {
+        let mut mapper = test_board(Board::CnRom);
+        mapper.cpu_write(0x8001, 3).unwrap();
+        assert_eq!(mapper.ppu_read(0x0000), 3);
+        assert_eq!(mapper.cpu_read(0xC000), Some(1));
+    }
+
+    #[test]
+    pub fn axrom() {
+        let mut mapper = test_board(Board::AxRom);
+        assert!(mapper.mirroring() == Mirroring::SingleScreenLower);
+        mapper.cpu_write(0x8001, 0b1_0011).unwrap();
+        assert_eq!(mapper.cpu_read(0x8000), Some(6));
+        assert_eq!(mapper.cpu_read(0xC000), Some(7));
+        assert!(mapper.mirroring() == Mirroring::SingleScreenUpper);
+    }
+
+    #[test]
+    pub fn gxrom_and_color_dreams() {
+        let mut mapper = test_board(Board::GxRom);
+        mapper.cpu_write(0x8001, 0b0010_0011).unwrap();
+        assert_eq!(mapper.cpu_read(0x8000), Some(4));
+        assert_eq!(mapper.ppu_read(0x0000), 3);
+
+        let mut mapper = test_board(Board::ColorDreams);
+        mapper.cpu_write(0x8001, 0b0101_0011).unwrap();
+        assert_eq!(mapper.cpu_read(0x8000), Some(6));
+        assert_eq!(mapper.ppu_read(0x0000), 5);
+    }
+
+    #[test]
+    pub fn bus_conflicts() {
+        let mut mapper = test_board(Board::UxRom);
+        // The ROM byte at $8000 is 0, so the write is lost
+        mapper.cpu_write(0x8000, 5).unwrap();
+        assert_eq!(mapper.cpu_read(0x8000), Some(0));
+
+        mapper.bus_conflicts = false;
+        mapper.cpu_write(0x8000, 5).unwrap();
+        assert_eq!(mapper.cpu_read(0x8000), Some(5));
+    }
+}
@@ -0,0 +1,291 @@
+use super::{ChrMemory, Mapper, PrgRam};
+use crate::{error::NesError, Mirroring};
+
+const PRG_BANK_SIZE: usize = 0x4000;
+const CHR_BANK_SIZE: usize = 0x1000;
+const PRG_RAM_BANK_SIZE: usize = 0x2000;
+// SUROM and SXROM carry 512KB of PRG ROM, split into two 256KB halves selected through the CHR bank registers
+const PRG_OUTER_BANK_SIZE: usize = 0x40000;
+
+/*
+ * Mapper 1. Registers are loaded one bit at a time through a 5 bit shift register, the fifth write copies
+ * it into the register picked by bits 13 and 14 of its address. Writing a value with bit 7 set clears the
+ * shift register and switches PRG mode 3 back on.
+ *
+ * $8000 Control
+ *     BIT 0-1: Mirroring (0: one screen lower; 1: one screen upper; 2: vertical; 3: horizontal)
+ *     BIT 2-3: PRG mode (0, 1: 32KB at $8000; 2: first bank fixed at $8000; 3: last bank fixed at $C000)
+ *     BIT 4: CHR mode (0: one 8KB bank; 1: two 4KB banks)
+ * $A000 CHR bank 0, $C000 CHR bank 1. On boards with 8KB of CHR the upper bits are wired elsewhere:
+ *     BIT 4 disables PRG-RAM on SNROM, and picks the 256KB PRG half on SUROM/SXROM
+ *     BIT 2-3 select the 8KB PRG-RAM bank on SXROM/SOROM
+ * $E000 PRG bank
+ *     BIT 0-3: 16KB PRG bank
+ *     BIT 4: PRG-RAM disable
+ */
+pub struct Mmc1 {
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    prg_ram: PrgRam,
+
+    shift_register: u8,
+    control: u8,
+    chr_bank_0: u8,
+    chr_bank_1: u8,
+    prg_bank: u8,
+}
+
+impl Mmc1 {
+    pub fn new(prg_rom: Vec<u8>, chr: impl Into<ChrMemory>) -> Self {
+        Mmc1 {
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: PrgRam::default(),
+            shift_register: 0b1_0000,
+            control: 0b0_1100,
+            chr_bank_0: 0,
+            chr_bank_1: 0,
+            prg_bank: 0,
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, data: u8) {
+        if data & 0b1000_0000 != 0 {
+            self.shift_register = 0b1_0000;
+            self.control |= 0b0_1100;
+            return;
+        }
+
+        // The starting 1 reaches bit 0 once four bits have been shifted in, so this write is the fifth
+        let complete = self.shift_register & 1 != 0;
+        self.shift_register = (self.shift_register >> 1) | ((data & 1) << 4);
+        if !complete {
+            return;
+        }
+
+        let value = self.shift_register;
+        match addr {
+            0x8000..=0x9FFF => self.control = value,
+            0xA000..=0xBFFF => self.chr_bank_0 = value,
+            0xC000..=0xDFFF => self.chr_bank_1 = value,
+            _ => self.prg_bank = value,
+        }
+        self.shift_register = 0b1_0000;
+    }
+
+    // Boards with a single 8KB CHR bank reuse the CHR bank lines for PRG and PRG-RAM banking
+    fn has_small_chr(&self) -> bool {
+        self.chr.len() <= 2 * CHR_BANK_SIZE
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        let snrom_disabled = self.has_small_chr()
+            && self.prg_rom.len() <= PRG_OUTER_BANK_SIZE
+            && self.chr_bank_0 & 0b1_0000 != 0;
+        !self.prg_ram.is_empty() && self.prg_bank & 0b1_0000 == 0 && !snrom_disabled
+    }
+
+    fn prg_ram_index(&self, addr: u16) -> usize {
+        let bank = match self.has_small_chr() {
+            true => (self.chr_bank_0 as usize >> 2) & 0b11,
+            false => 0,
+        };
+        (bank * PRG_RAM_BANK_SIZE + (addr - 0x6000) as usize) % self.prg_ram.len()
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        let outer_bank = match self.prg_rom.len() > PRG_OUTER_BANK_SIZE {
+            true => (self.chr_bank_0 as usize >> 4) & 1,
+            false => 0,
+        };
+        let banks_per_outer = (self.prg_rom.len() / PRG_BANK_SIZE).min(PRG_OUTER_BANK_SIZE / PRG_BANK_SIZE);
+        let select = (self.prg_bank & 0b1111) as usize;
+        let last_bank = banks_per_outer - 1;
+
+        let slot = (addr as usize - 0x8000) / PRG_BANK_SIZE;
+        let bank = match ((self.control >> 2) & 0b11, slot) {
+            (0 | 1, _) => (select & !1) + slot,
+            (2, 0) => 0,
+            (2, _) => select,
+            (_, 0) => select,
+            (_, _) => last_bank,
+        };
+        let bank = outer_bank * banks_per_outer + bank % banks_per_outer;
+        (bank * PRG_BANK_SIZE + (addr as usize % PRG_BANK_SIZE)) % self.prg_rom.len()
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let slot = addr as usize / CHR_BANK_SIZE;
+        let bank = match (self.control & 0b1_0000 != 0, slot) {
+            (false, _) => (self.chr_bank_0 & !1) as usize + slot,
+            (true, 0) => self.chr_bank_0 as usize,
+            (true, _) => self.chr_bank_1 as usize,
+        };
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => Some(self.prg_ram.read(self.prg_ram_index(addr))),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => {
+                let index = self.prg_ram_index(addr);
+                self.prg_ram.write(index, data);
+            },
+            // Writes to disabled PRG-RAM go nowhere
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => (),
+            0x8000..=0xFFFF => self.write_register(addr, data),
+            _ => return Err(NesError::UnmappedAccess { addr }),
+        }
+        Ok(())
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr.read(self.chr_index(addr))
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(self.chr_index(addr), addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0b11 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+#[cfg(test)]
+mod mmc1_tests {
+    use super::*;
+
+    // Every 16KB PRG bank and 4KB CHR bank starts with its own bank number
+    fn test_mmc1(prg_banks: usize, chr_banks: usize) -> Mmc1 {
+        let mut prg_rom = vec![0; prg_banks * PRG_BANK_SIZE];
+        for bank in 0..prg_banks {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut chr_rom = vec![0; chr_banks * CHR_BANK_SIZE];
+        for bank in 0..chr_banks {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Mmc1::new(prg_rom, chr_rom)
+    }
+
+    fn write_serial(mmc1: &mut Mmc1, addr: u16, value: u8) {
+        for bit in 0..5 {
+            mmc1.cpu_write(addr, (value >> bit) & 1).unwrap();
+        }
+    }
+
+    #[test]
+    pub fn shift_register_loads_after_five_writes() {
+        let mut mmc1 = test_mmc1(8, 2);
+        for bit in 0..4 {
+            mmc1.cpu_write(0x8000, (0b0_0010 >> bit) & 1).unwrap();
+            assert!(mmc1.mirroring() == Mirroring::SingleScreenLower);
+        }
+        mmc1.cpu_write(0x8000, 0).unwrap();
+        assert!(mmc1.mirroring() == Mirroring::Vertical);
+    }
+
+    #[test]
+    pub fn reset_bit_clears_shift_register() {
+        let mut mmc1 = test_mmc1(8, 2);
+        write_serial(&mut mmc1, 0x8000, 0b0_0011);
+        mmc1.cpu_write(0x8000, 1).unwrap();
+        mmc1.cpu_write(0x8000, 1).unwrap();
+        mmc1.cpu_write(0x8000, 0x80).unwrap();
+        // The partial writes are dropped and PRG mode 3 is set again
+        assert_eq!(mmc1.control, 0b0_1111);
+        write_serial(&mut mmc1, 0xE000, 0b0_0010);
+        assert_eq!(mmc1.prg_bank, 0b0_0010);
+    }
+
+    #[test]
+    pub fn prg_banking_modes() {
+        let mut mmc1 = test_mmc1(8, 2);
+        // Mode 3 at power up, last bank fixed at $C000
+        write_serial(&mut mmc1, 0xE000, 3);
+        assert_eq!(mmc1.cpu_read(0x8000), Some(3));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(7));
+
+        write_serial(&mut mmc1, 0x8000, 0b0_1000);
+        assert_eq!(mmc1.cpu_read(0x8000), Some(0));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(3));
+
+        // 32KB mode ignores the low bit of the bank
+        write_serial(&mut mmc1, 0x8000, 0b0_0000);
+        assert_eq!(mmc1.cpu_read(0x8000), Some(2));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(3));
+    }
+
+    #[test]
+    pub fn chr_banking_modes() {
+        let mut mmc1 = test_mmc1(2, 8);
+        write_serial(&mut mmc1, 0xA000, 5);
+        write_serial(&mut mmc1, 0xC000, 2);
+        assert_eq!(mmc1.ppu_read(0x0000), 4);
+        assert_eq!(mmc1.ppu_read(0x1000), 5);
+
+        write_serial(&mut mmc1, 0x8000, 0b1_0000);
+        assert_eq!(mmc1.ppu_read(0x0000), 5);
+        assert_eq!(mmc1.ppu_read(0x1000), 2);
+    }
+
+    #[test]
+    pub fn prg_ram() {
+        let mut mmc1 = test_mmc1(2, 8);
+        mmc1.cpu_write(0x6010, 0x42).unwrap();
+        assert_eq!(mmc1.cpu_read(0x6010), Some(0x42));
+
+        write_serial(&mut mmc1, 0xE000, 0b1_0000);
+        assert_eq!(mmc1.cpu_read(0x6010), None);
+        mmc1.cpu_write(0x6010, 0x11).unwrap();
+        write_serial(&mut mmc1, 0xE000, 0);
+        assert_eq!(mmc1.cpu_read(0x6010), Some(0x42));
+
+        let mut mmc1 = Mmc1::new(vec![0; PRG_BANK_SIZE], vec![0; 0x2000]);
+        *mmc1.prg_ram().unwrap() = PrgRam::new(0, false);
+        assert_eq!(mmc1.cpu_read(0x6010), None);
+        assert!(mmc1.cpu_write(0x6010, 0).is_err());
+    }
+
+    #[test]
+    pub fn snrom_disables_prg_ram_through_chr_bank() {
+        let mut mmc1 = Mmc1::new(vec![0; 16 * PRG_BANK_SIZE], vec![]);
+        mmc1.cpu_write(0x6000, 0x42).unwrap();
+        write_serial(&mut mmc1, 0xA000, 0b1_0000);
+        assert_eq!(mmc1.cpu_read(0x6000), None);
+    }
+
+    #[test]
+    pub fn surom_selects_prg_half_through_chr_bank() {
+        let mut mmc1 = test_mmc1(32, 0);
+        write_serial(&mut mmc1, 0xE000, 2);
+        assert_eq!(mmc1.cpu_read(0x8000), Some(2));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(15));
+
+        write_serial(&mut mmc1, 0xA000, 0b1_0000);
+        assert_eq!(mmc1.cpu_read(0x8000), Some(18));
+        assert_eq!(mmc1.cpu_read(0xC000), Some(31));
+        // PRG-RAM stays enabled on SUROM
+        mmc1.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(mmc1.cpu_read(0x6000), Some(0x42));
+    }
+}
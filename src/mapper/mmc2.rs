@@ -0,0 +1,225 @@
+use super::{ChrMemory, Mapper, PrgRam};
+use crate::{error::NesError, Mirroring};
+
+const CHR_BANK_SIZE: usize = 0x1000;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Chip {
+    // Mapper 9. Switchable 8KB bank at $8000, the last three 8KB banks fixed at $A000
+    Mmc2,
+    // Mapper 10. Switchable 16KB bank at $8000, the last 16KB bank fixed at $C000, 8KB PRG-RAM at $6000
+    Mmc4,
+}
+
+impl Chip {
+    fn prg_bank_size(&self) -> usize {
+        match self {
+            Chip::Mmc2 => 0x2000,
+            Chip::Mmc4 => 0x4000,
+        }
+    }
+}
+
+/*
+ * Each pattern table half has two CHR banks, one for each state of a latch. The latches flip when the PPU
+ * fetches tile $FD or $FE, so a game can switch banks partway through a scanline by placing those tiles.
+ *
+ * $A000 PRG bank
+ * $B000 CHR bank for $0000 when latch 0 is $FD, $C000 when it is $FE
+ * $D000 CHR bank for $1000 when latch 1 is $FD, $E000 when it is $FE
+ * $F000 Mirroring (0: vertical; 1: horizontal)
+ */
+pub struct Mmc2 {
+    chip: Chip,
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    prg_ram: PrgRam,
+    mirroring: Mirroring,
+
+    prg_bank: usize,
+    // Indexed by [pattern table half][latch is $FE]
+    chr_banks: [[usize; 2]; 2],
+    latches: [bool; 2],
+    // The bank switches once the fetch that triggered it is done
+    pending_latch: Option<(usize, bool)>,
+}
+
+impl Mmc2 {
+    pub fn new(chip: Chip, prg_rom: Vec<u8>, chr: impl Into<ChrMemory>, mirroring: Mirroring) -> Self {
+        Mmc2 {
+            chip,
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: match chip {
+                Chip::Mmc2 => PrgRam::new(0, false),
+                Chip::Mmc4 => PrgRam::default(),
+            },
+            mirroring,
+            prg_bank: 0,
+            chr_banks: [[0; 2]; 2],
+            latches: [false; 2],
+            pending_latch: None,
+        }
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        let bank_size = self.chip.prg_bank_size();
+        let offset = (addr - 0x8000) as usize;
+        let index = match offset < bank_size {
+            true => self.prg_bank * bank_size + offset,
+            // Everything past the switchable bank is the end of the ROM
+            false => self.prg_rom.len() - (0x8000 - offset),
+        };
+        index % self.prg_rom.len()
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let half = (addr as usize / CHR_BANK_SIZE) & 1;
+        let bank = self.chr_banks[half][self.latches[half] as usize];
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    // MMC2 only watches the first row of tile $FD and $FE in the lower half, everything else watches the
+    // whole tile from $xFD8 to $xFEF
+    fn latch_for(&self, addr: u16) -> Option<(usize, bool)> {
+        let half = (addr as usize / CHR_BANK_SIZE) & 1;
+        match (addr & 0x0FF8, self.chip, half) {
+            (0x0FD8, Chip::Mmc2, 0) => (addr == 0x0FD8).then_some((0, false)),
+            (0x0FE8, Chip::Mmc2, 0) => (addr == 0x0FE8).then_some((0, true)),
+            (0x0FD8, _, _) => Some((half, false)),
+            (0x0FE8, _, _) => Some((half, true)),
+            _ => None,
+        }
+    }
+
+    fn apply_pending_latch(&mut self) {
+        if let Some((half, value)) = self.pending_latch.take() {
+            self.latches[half] = value;
+        }
+    }
+}
+
+impl Mapper for Mmc2 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => Some(self.prg_ram.read((addr - 0x6000) as usize)),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        let data = data as usize;
+        match addr {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => self.prg_ram.write((addr - 0x6000) as usize, data as u8),
+            0xA000..=0xAFFF => self.prg_bank = data & 0x0F,
+            0xB000..=0xBFFF => self.chr_banks[0][0] = data & 0x1F,
+            0xC000..=0xCFFF => self.chr_banks[0][1] = data & 0x1F,
+            0xD000..=0xDFFF => self.chr_banks[1][0] = data & 0x1F,
+            0xE000..=0xEFFF => self.chr_banks[1][1] = data & 0x1F,
+            0xF000..=0xFFFF => {
+                self.mirroring = match data & 1 {
+                    0 => Mirroring::Vertical,
+                    _ => Mirroring::Horizontal,
+                };
+            },
+            0x8000..=0x9FFF => return Err(NesError::RomWrite { addr, data: data as u8 }),
+            _ => return Err(NesError::UnmappedAccess { addr }),
+        }
+        Ok(())
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        let data = self.chr.read(self.chr_index(addr));
+        self.apply_pending_latch();
+        data
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(self.chr_index(addr), addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring.clone()
+    }
+
+    fn ppu_address(&mut self, addr: u16) {
+        self.apply_pending_latch();
+        if addr < 0x2000 {
+            self.pending_latch = self.latch_for(addr);
+        }
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+#[cfg(test)]
+mod mmc2_tests {
+    use super::*;
+
+    // Every 8KB PRG bank and 4KB CHR bank starts with its own bank number
+    fn test_mmc2(chip: Chip) -> Mmc2 {
+        let mut prg_rom = vec![0; 0x20000];
+        for bank in 0..16 {
+            prg_rom[bank * 0x2000] = bank as u8;
+        }
+        let mut chr_rom = vec![0; 32 * CHR_BANK_SIZE];
+        for bank in 0..32 {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Mmc2::new(chip, prg_rom, chr_rom, Mirroring::Vertical)
+    }
+
+    fn fetch(mapper: &mut Mmc2, addr: u16) -> u8 {
+        mapper.ppu_address(addr);
+        mapper.ppu_read(addr)
+    }
+
+    #[test]
+    pub fn prg_banking() {
+        let mut mmc2 = test_mmc2(Chip::Mmc2);
+        mmc2.cpu_write(0xA000, 3).unwrap();
+        assert_eq!(mmc2.cpu_read(0x8000), Some(3));
+        assert_eq!(mmc2.cpu_read(0xA000), Some(13));
+        assert_eq!(mmc2.cpu_read(0xE000), Some(15));
+
+        let mut mmc4 = test_mmc2(Chip::Mmc4);
+        mmc4.cpu_write(0xA000, 3).unwrap();
+        assert_eq!(mmc4.cpu_read(0x8000), Some(6));
+        assert_eq!(mmc4.cpu_read(0xC000), Some(14));
+        mmc4.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(mmc4.cpu_read(0x6000), Some(0x42));
+    }
+
+    #[test]
+    pub fn fetching_fd_and_fe_switches_chr_after_the_fetch() {
+        let mut mmc2 = test_mmc2(Chip::Mmc2);
+        mmc2.cpu_write(0xB000, 4).unwrap();
+        mmc2.cpu_write(0xC000, 5).unwrap();
+        mmc2.cpu_write(0xD000, 6).unwrap();
+        mmc2.cpu_write(0xE000, 7).unwrap();
+        assert_eq!(fetch(&mut mmc2, 0x0000), 4);
+
+        // The tile that trips the latch is still drawn from the old bank
+        fetch(&mut mmc2, 0x0FE8);
+        assert_eq!(fetch(&mut mmc2, 0x0000), 5);
+        // MMC2 ignores the other rows of the tile in the lower half
+        fetch(&mut mmc2, 0x0FD9);
+        assert_eq!(fetch(&mut mmc2, 0x0000), 5);
+
+        fetch(&mut mmc2, 0x1FEA);
+        assert_eq!(fetch(&mut mmc2, 0x1000), 7);
+        fetch(&mut mmc2, 0x1FDF);
+        assert_eq!(fetch(&mut mmc2, 0x1000), 6);
+    }
+
+    #[test]
+    pub fn mmc4_watches_whole_tile() {
+        let mut mmc4 = test_mmc2(Chip::Mmc4);
+        mmc4.cpu_write(0xC000, 5).unwrap();
+        fetch(&mut mmc4, 0x0FEC);
+        assert_eq!(fetch(&mut mmc4, 0x0000), 5);
+    }
+}
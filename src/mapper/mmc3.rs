@@ -0,0 +1,425 @@
+use super::{ChrMemory, Mapper, PrgRam};
+use crate::{error::NesError, Mirroring};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+// A12 has to stay low for about three CPU cycles before a rise clocks the counter again, which filters out
+// the quick toggles between sprite pattern fetches
+const A12_FILTER_DOTS: u64 = 10;
+
+/*
+ * Mapper 4. Registers are picked by the address range and whether the address is even or odd.
+ *
+ * $8000 Bank select
+ *     BIT 0-2: Bank register (R0 - R7) the next bank data write goes to
+ *     BIT 6: PRG mode (0: R6 at $8000, second to last bank at $C000; 1: the other way around)
+ *     BIT 7: CHR inversion (0: 2KB banks R0, R1 at $0000; 1: 2KB banks at $1000)
+ * $8001 Bank data
+ * $A000 Mirroring (0: vertical; 1: horizontal), ignored on four screen boards
+ * $A001 PRG-RAM protect (BIT 6: write protect; BIT 7: enable)
+ * $C000 IRQ latch, $C001 IRQ reload, $E000 IRQ disable and acknowledge, $E001 IRQ enable
+ *
+ * The scanline counter is clocked by rising edges of PPU A12. With backgrounds at $0000 and sprites at
+ * $1000 that happens once per scanline, when sprite patterns are fetched. See IrqVariant for when the
+ * counter reaching 0 raises an IRQ.
+ */
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum IrqVariant {
+    // Sharp MMC3B/C: any clock that leaves the counter at 0 fires, so a latch of 0 fires every scanline
+    Sharp,
+    // NEC MMC3A (NES 2.0 submapper 4): only a decrement to 0 or a reload requested through $C001 fires
+    Nec,
+}
+
+impl IrqVariant {
+    pub fn for_submapper(submapper: u8) -> Self {
+        match submapper {
+            4 => IrqVariant::Nec,
+            _ => IrqVariant::Sharp,
+        }
+    }
+}
+
+pub struct Mmc3 {
+    irq_variant: IrqVariant,
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    prg_ram: PrgRam,
+    four_screen: bool,
+
+    bank_select: u8,
+    bank_registers: [u8; 8],
+    mirroring: Mirroring,
+    prg_ram_protect: u8,
+
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+}
+
+impl Mmc3 {
+    pub fn new(submapper: u8, prg_rom: Vec<u8>, chr: impl Into<ChrMemory>, mirroring: Mirroring) -> Self {
+        Mmc3 {
+            irq_variant: IrqVariant::for_submapper(submapper),
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: PrgRam::default(),
+            four_screen: mirroring == Mirroring::FourScreen,
+            bank_select: 0,
+            bank_registers: [0; 8],
+            mirroring,
+            // Plenty of games never touch $A001, so the RAM starts out enabled
+            prg_ram_protect: 0b1000_0000,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+        }
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        let bank_count = self.prg_rom.len() / PRG_BANK_SIZE;
+        // Images smaller than 16KB have no second to last bank, use the first one instead of underflowing
+        let second_last = bank_count.saturating_sub(2);
+        let swapped = self.bank_select & 0b0100_0000 != 0;
+        let bank = match ((addr - 0x8000) as usize / PRG_BANK_SIZE, swapped) {
+            (0, false) | (2, true) => self.bank_registers[6] as usize,
+            (0, true) | (2, false) => second_last,
+            (1, _) => self.bank_registers[7] as usize,
+            _ => bank_count - 1,
+        };
+        (bank % bank_count) * PRG_BANK_SIZE + addr as usize % PRG_BANK_SIZE
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        // CHR inversion swaps the two pattern table halves
+        let addr = match self.bank_select & 0b1000_0000 != 0 {
+            true => addr ^ 0x1000,
+            false => addr,
+        };
+        let bank = match addr as usize / CHR_BANK_SIZE {
+            slot @ 0..=3 => (self.bank_registers[slot / 2] & 0xFE) as usize + slot % 2,
+            slot => self.bank_registers[slot - 2] as usize,
+        };
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_ram_protect & 0b1000_0000 != 0
+    }
+
+    fn clock_irq_counter(&mut self) {
+        let was_zero = self.irq_counter == 0;
+        let forced_reload = self.irq_reload;
+        if was_zero || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+        let fires = match self.irq_variant {
+            IrqVariant::Sharp => self.irq_counter == 0,
+            IrqVariant::Nec => self.irq_counter == 0 && (!was_zero || forced_reload),
+        };
+        if fires && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => Some(self.prg_ram.read((addr - 0x6000) as usize)),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        let even = addr.is_multiple_of(2);
+        match addr {
+            0x6000..=0x7FFF => {
+                // Writes to disabled or write protected PRG-RAM go nowhere
+                if self.prg_ram_enabled() && self.prg_ram_protect & 0b0100_0000 == 0 {
+                    self.prg_ram.write((addr - 0x6000) as usize, data);
+                }
+            },
+            0x8000..=0x9FFF if even => self.bank_select = data,
+            0x8000..=0x9FFF => self.bank_registers[(self.bank_select & 0b111) as usize] = data,
+            0xA000..=0xBFFF if even => {
+                if !self.four_screen {
+                    self.mirroring = match data & 1 {
+                        0 => Mirroring::Vertical,
+                        _ => Mirroring::Horizontal,
+                    };
+                }
+            },
+            0xA000..=0xBFFF => self.prg_ram_protect = data,
+            0xC000..=0xDFFF if even => self.irq_latch = data,
+            0xC000..=0xDFFF => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            },
+            0xE000..=0xFFFF if even => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            },
+            0xE000..=0xFFFF => self.irq_enabled = true,
+            _ => return Err(NesError::UnmappedAccess { addr }),
+        }
+        Ok(())
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr.read(self.chr_index(addr))
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(self.chr_index(addr), addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring.clone()
+    }
+
+    fn ppu_a12_rise(&mut self, low_dots: u64) {
+        if low_dots >= A12_FILTER_DOTS {
+            self.clock_irq_counter();
+        }
+    }
+
+    fn irq_active(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+#[cfg(test)]
+mod mmc3_tests {
+    use super::*;
+
+    // Every 8KB PRG bank and 1KB CHR bank starts with its own bank number
+    fn test_mmc3() -> Mmc3 {
+        let mut prg_rom = vec![0; 16 * PRG_BANK_SIZE];
+        for bank in 0..16 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut chr_rom = vec![0; 32 * CHR_BANK_SIZE];
+        for bank in 0..32 {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Mmc3::new(0, prg_rom, chr_rom, Mirroring::Vertical)
+    }
+
+    fn set_bank(mmc3: &mut Mmc3, mode: u8, register: u8, bank: u8) {
+        mmc3.cpu_write(0x8000, mode | register).unwrap();
+        mmc3.cpu_write(0x8001, bank).unwrap();
+    }
+
+    #[test]
+    pub fn prg_banking_modes() {
+        let mut mmc3 = test_mmc3();
+        set_bank(&mut mmc3, 0, 6, 3);
+        set_bank(&mut mmc3, 0, 7, 5);
+        assert_eq!(mmc3.cpu_read(0x8000), Some(3));
+        assert_eq!(mmc3.cpu_read(0xA000), Some(5));
+        assert_eq!(mmc3.cpu_read(0xC000), Some(14));
+        assert_eq!(mmc3.cpu_read(0xE000), Some(15));
+
+        mmc3.cpu_write(0x8000, 0b0100_0000).unwrap();
+        assert_eq!(mmc3.cpu_read(0x8000), Some(14));
+        assert_eq!(mmc3.cpu_read(0xC000), Some(3));
+    }
+
+    #[test]
+    pub fn chr_banking_and_inversion() {
+        let mut mmc3 = test_mmc3();
+        // 2KB banks ignore the low bit
+        set_bank(&mut mmc3, 0, 0, 9);
+        set_bank(&mut mmc3, 0, 5, 20);
+        assert_eq!(mmc3.ppu_read(0x0000), 8);
+        assert_eq!(mmc3.ppu_read(0x0400), 9);
+        assert_eq!(mmc3.ppu_read(0x1C00), 20);
+
+        mmc3.cpu_write(0x8000, 0b1000_0000).unwrap();
+        assert_eq!(mmc3.ppu_read(0x1000), 8);
+        assert_eq!(mmc3.ppu_read(0x0C00), 20);
+    }
+
+    #[test]
+    pub fn mirroring_and_prg_ram_protect() {
+        let mut mmc3 = test_mmc3();
+        mmc3.cpu_write(0xA000, 1).unwrap();
+        assert!(mmc3.mirroring() == Mirroring::Horizontal);
+
+        mmc3.cpu_write(0x6000, 0x42).unwrap();
+        mmc3.cpu_write(0xA001, 0b1100_0000).unwrap();
+        mmc3.cpu_write(0x6000, 0x11).unwrap();
+        assert_eq!(mmc3.cpu_read(0x6000), Some(0x42));
+        mmc3.cpu_write(0xA001, 0).unwrap();
+        assert_eq!(mmc3.cpu_read(0x6000), None);
+
+        let mut mmc3 = Mmc3::new(0, vec![0; 4 * PRG_BANK_SIZE], vec![], Mirroring::FourScreen);
+        mmc3.cpu_write(0xA000, 1).unwrap();
+        assert!(mmc3.mirroring() == Mirroring::FourScreen);
+    }
+
+    #[test]
+    pub fn irq_counter() {
+        let mut mmc3 = test_mmc3();
+        mmc3.cpu_write(0xC000, 2).unwrap();
+        mmc3.cpu_write(0xC001, 0).unwrap();
+        mmc3.cpu_write(0xE001, 0).unwrap();
+
+        // Reload to 2, then 1, then 0 fires
+        mmc3.ppu_a12_rise(100);
+        mmc3.ppu_a12_rise(100);
+        assert!(!mmc3.irq_active());
+        // Quick toggles are filtered out
+        mmc3.ppu_a12_rise(2);
+        assert!(!mmc3.irq_active());
+        mmc3.ppu_a12_rise(100);
+        assert!(mmc3.irq_active());
+
+        mmc3.cpu_write(0xE000, 0).unwrap();
+        assert!(!mmc3.irq_active());
+        // The counter reloads from the latch once it hits 0, and disabled IRQs don't fire
+        mmc3.ppu_a12_rise(100);
+        assert_eq!(mmc3.irq_counter, 2);
+        mmc3.ppu_a12_rise(100);
+        mmc3.ppu_a12_rise(100);
+        assert!(!mmc3.irq_active());
+    }
+
+    // A latch of 0 fires on every clock while enabled
+    #[test]
+    pub fn irq_with_zero_latch() {
+        let mut mmc3 = test_mmc3();
+        mmc3.cpu_write(0xE001, 0).unwrap();
+        mmc3.ppu_a12_rise(100);
+        assert!(mmc3.irq_active());
+    }
+
+    // The checks 5-MMC3 and 6-MMC3_alt of mmc3_test make: with a latch of 0, Sharp chips fire on every clock
+    // while NEC chips only fire on the clock after a $C001 write
+    #[test]
+    pub fn irq_variants_with_zero_latch() {
+        for (submapper, fires_again) in [(0, true), (4, false)] {
+            let mut mmc3 = test_mmc3();
+            mmc3.irq_variant = IrqVariant::for_submapper(submapper);
+            mmc3.cpu_write(0xC000, 0).unwrap();
+            mmc3.cpu_write(0xC001, 0).unwrap();
+            mmc3.cpu_write(0xE001, 0).unwrap();
+            mmc3.ppu_a12_rise(100);
+            assert!(mmc3.irq_active());
+            mmc3.cpu_write(0xE000, 0).unwrap();
+            mmc3.cpu_write(0xE001, 0).unwrap();
+            mmc3.ppu_a12_rise(100);
+            assert_eq!(mmc3.irq_active(), fires_again);
+        }
+    }
+
+    // Counter details from 2-details: the latch only takes effect on a reload, $C001 forces one on the next
+    // clock, a latch of 255 counts down from 255, and $E000 acknowledges
+    #[test]
+    pub fn irq_counter_details() {
+        for submapper in [0, 4] {
+            let mut mmc3 = test_mmc3();
+            mmc3.irq_variant = IrqVariant::for_submapper(submapper);
+            mmc3.cpu_write(0xE001, 0).unwrap();
+            mmc3.cpu_write(0xC000, 3).unwrap();
+            mmc3.cpu_write(0xC001, 0).unwrap();
+            mmc3.ppu_a12_rise(100);
+            assert_eq!(mmc3.irq_counter, 3);
+            // A new latch waits for the next reload
+            mmc3.cpu_write(0xC000, 255).unwrap();
+            mmc3.ppu_a12_rise(100);
+            assert_eq!(mmc3.irq_counter, 2);
+            mmc3.cpu_write(0xC001, 0).unwrap();
+            mmc3.ppu_a12_rise(100);
+            assert_eq!(mmc3.irq_counter, 255);
+            for _ in 0..254 {
+                mmc3.ppu_a12_rise(100);
+            }
+            assert!(!mmc3.irq_active());
+            mmc3.ppu_a12_rise(100);
+            assert!(mmc3.irq_active());
+            mmc3.cpu_write(0xE000, 0).unwrap();
+            assert!(!mmc3.irq_active());
+        }
+    }
+
+    #[test]
+    pub fn eight_kb_prg() {
+        let mut prg_rom = vec![0; PRG_BANK_SIZE];
+        prg_rom[0x10] = 0xAB;
+        let mut mmc3 = Mmc3::new(0, prg_rom, vec![0; 0x2000], Mirroring::Vertical);
+        for addr in [0x8010, 0xA010, 0xC010, 0xE010] {
+            assert_eq!(mmc3.cpu_read(addr), Some(0xAB));
+        }
+        mmc3.cpu_write(0x8000, 0b0100_0000).unwrap();
+        assert_eq!(mmc3.cpu_read(0x8010), Some(0xAB));
+    }
+
+    // Runs blargg's mmc3_test roms from ./test_roms/mmc3_test, which aren't part of the repo:
+    // cargo test mmc3_test_suite -- --ignored
+    // 6-MMC3_alt tests the NEC chip, so it runs as submapper 4.
+    // Each rom reports through PRG-RAM: $6001 - $6003 hold DE B0 61 once $6000 has a valid status, which stays
+    // $80 while the test runs and is 0 when it passes. $6004 on has the message.
+    #[test]
+    #[ignore]
+    pub fn mmc3_test_suite() {
+        use crate::{cpu::CPU, rom::Rom, MemAccess};
+
+        let mut roms: Vec<_> = std::fs::read_dir("./test_roms/mmc3_test")
+            .expect("mmc3_test roms not found")
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "nes"))
+            .collect();
+        roms.sort();
+        assert!(!roms.is_empty());
+
+        let mut failures = Vec::new();
+        for path in roms {
+            let mut rom = Rom::from_rom(&path).unwrap();
+            if path.to_string_lossy().contains("alt") {
+                rom.submapper = 4;
+            }
+            let mut cpu = CPU::new();
+            cpu.load_rom(rom).unwrap();
+            cpu.reset();
+            let mut status = None;
+            for _ in 0..50_000_000 {
+                if let Err(error) = cpu.step() {
+                    status = Some(format!("stopped on {error}"));
+                    break;
+                }
+                let signature = [cpu.mem_read(0x6001), cpu.mem_read(0x6002), cpu.mem_read(0x6003)];
+                let code = cpu.mem_read(0x6000);
+                if signature == [0xDE, 0xB0, 0x61] && code < 0x80 {
+                    let mut message = String::new();
+                    let mut addr = 0x6004;
+                    while cpu.mem_read(addr) != 0 && addr < 0x7000 {
+                        message.push(cpu.mem_read(addr) as char);
+                        addr += 1;
+                    }
+                    status = Some(format!("result {code}: {}", message.trim()));
+                    break;
+                }
+            }
+            let status = status.unwrap_or_else(|| String::from("timed out"));
+            println!("{}: {status}", path.display());
+            if !status.starts_with("result 0") {
+                failures.push(path);
+            }
+        }
+        assert!(failures.is_empty(), "failed: {failures:?}");
+    }
+}
@@ -0,0 +1,292 @@
+mod chr;
+mod prg_ram;
+pub mod discrete;
+pub mod mmc1;
+pub mod mmc2;
+pub mod mmc3;
+pub mod nrom;
+pub mod vrc;
+
+use std::{cell::RefCell, rc::Rc};
+
+use crate::error::RomError;
+
+pub use chr::{ChrMemory, DEFAULT_CHR_RAM_SIZE};
+pub use prg_ram::{PrgRam, DEFAULT_PRG_RAM_SIZE};
+use discrete::{Board, Discrete};
+use mmc1::Mmc1;
+use mmc2::{Chip, Mmc2};
+use mmc3::Mmc3;
+use nrom::Nrom;
+use vrc::{vrc2_4::Vrc2_4, vrc6::Vrc6, vrc7::Vrc7};
+
+use crate::{error::NesError, rom::Rom, Mirroring};
+
+// Where a rom's trainer gets copied to in PRG-RAM
+const TRAINER_ADDR: usize = 0x7000;
+
+// The cartridge as seen by the CPU and PPU buses. Mappers own PRG and CHR memory, decide how it is banked
+// into the address space, and can change the nametable mirroring or raise IRQs from their registers.
+pub trait Mapper {
+    // CPU reads from $4020 - $FFFF. None means nothing on the cartridge answers at that address.
+    fn cpu_read(&mut self, addr: u16) -> Option<u8>;
+
+    // CPU writes to $4020 - $FFFF, where most mappers keep their bank registers
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError>;
+
+    // PPU reads from the pattern tables at $0000 - $1FFF
+    fn ppu_read(&mut self, addr: u16) -> u8;
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError>;
+
+    fn mirroring(&self) -> Mirroring;
+
+    // Called with the address of every PPU read or write, before the access itself, for mappers that watch
+    // which pattern table tiles are fetched
+    fn ppu_address(&mut self, _addr: u16) {}
+
+    // Called when PPU A12 goes from low to high, with the number of dots it stayed low beforehand.
+    // Scanline counters use the low time to filter out the toggles between sprite pattern fetches.
+    fn ppu_a12_rise(&mut self, _low_dots: u64) {}
+
+    // Called after every CPU instruction with the cycles it took, for mappers that count CPU cycles
+    fn cpu_clock(&mut self, _cycles: usize) {}
+
+    fn irq_active(&self) -> bool {
+        false
+    }
+
+    // Work RAM at $6000 - $7FFF, if the board has any
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        None
+    }
+
+    // Current output of the cartridge's sound chip for the APU mixer, 1.0 being its loudest
+    fn expansion_audio(&self) -> f32 {
+        0.0
+    }
+}
+
+// The Bus and the PPU both hold on to the cartridge
+pub type SharedMapper = Rc<RefCell<dyn Mapper>>;
+
+pub fn shared<M: Mapper + 'static>(mapper: M) -> SharedMapper {
+    Rc::new(RefCell::new(mapper))
+}
+
+// Every mapper number from_rom can build
+pub const SUPPORTED_MAPPERS: &[u16] = &[0, 1, 2, 3, 4, 7, 9, 10, 11, 21, 22, 23, 24, 25, 26, 66, 85];
+
+pub fn is_supported(mapper: u16) -> bool {
+    SUPPORTED_MAPPERS.contains(&mapper)
+}
+
+// The PRG ROM bank size of each board and the smallest PRG ROM its fixed banks work with
+fn prg_size_requirements(mapper: u16) -> (usize, usize) {
+    match mapper {
+        1 | 2 | 3 | 7 | 11 | 66 => (0x4000, 0x4000),
+        // The banks past the switchable one are the end of a 32KB window
+        9 => (0x2000, 0x8000),
+        10 => (0x4000, 0x8000),
+        // The second to last bank is fixed
+        21 | 22 | 23 | 25 => (0x2000, 0x4000),
+        _ => (0x2000, 0x2000),
+    }
+}
+
+// NES 2.0 headers can declare PRG ROM of any size, the banking math needs whole banks
+pub fn check_prg_size(mapper: u16, len: usize) -> Result<(), RomError> {
+    let (bank_size, minimum) = prg_size_requirements(mapper);
+    match len >= minimum && len.is_multiple_of(bank_size) {
+        true => Ok(()),
+        false => Err(RomError::InvalidPrgSize { mapper, len }),
+    }
+}
+
+// Builds the mapper for the board the rom header asks for
+pub fn from_rom(rom: Rom) -> Result<SharedMapper, RomError> {
+    if !is_supported(rom.mapper) {
+        return Err(RomError::UnsupportedMapper { mapper: rom.mapper, submapper: rom.submapper });
+    }
+    check_prg_size(rom.mapper, rom.prg_rom.len())?;
+
+    // Roms without CHR ROM get CHR-RAM, sized by the NES 2.0 header when it has one
+    let chr_ram_size = match rom.metadata.chr_ram_size + rom.metadata.chr_nvram_size {
+        0 => DEFAULT_CHR_RAM_SIZE,
+        size => size,
+    };
+    let chr = ChrMemory::new(rom.chr_rom, chr_ram_size);
+    let mapper = match rom.mapper {
+        0 => shared(Nrom::new(rom.prg_rom, chr, rom.screen_mirroring)),
+        1 => shared(Mmc1::new(rom.prg_rom, chr)),
+        2 => shared(Discrete::new(Board::UxRom, rom.submapper, rom.prg_rom, chr, rom.screen_mirroring)),
+        3 => shared(Discrete::new(Board::CnRom, rom.submapper, rom.prg_rom, chr, rom.screen_mirroring)),
+        4 => shared(Mmc3::new(rom.submapper, rom.prg_rom, chr, rom.screen_mirroring)),
+        7 => shared(Discrete::new(Board::AxRom, rom.submapper, rom.prg_rom, chr, rom.screen_mirroring)),
+        9 => shared(Mmc2::new(Chip::Mmc2, rom.prg_rom, chr, rom.screen_mirroring)),
+        10 => shared(Mmc2::new(Chip::Mmc4, rom.prg_rom, chr, rom.screen_mirroring)),
+        11 => shared(Discrete::new(Board::ColorDreams, rom.submapper, rom.prg_rom, chr, rom.screen_mirroring)),
+        21 | 22 | 23 | 25 => shared(Vrc2_4::new(rom.mapper, rom.submapper, rom.prg_rom, chr)),
+        24 | 26 => shared(Vrc6::new(rom.mapper, rom.prg_rom, chr)),
+        66 => shared(Discrete::new(Board::GxRom, rom.submapper, rom.prg_rom, chr, rom.screen_mirroring)),
+        85 => shared(Vrc7::new(rom.submapper, rom.prg_rom, chr)),
+        mapper => return Err(RomError::UnsupportedMapper { mapper, submapper: rom.submapper }),
+    };
+
+    // The battery bit decides whether the work RAM gets saved. NES 2.0 headers also give its size, otherwise
+    // the board's usual size is kept. Trainers expect to be at $7000 when the game boots, so a rom with one
+    // gets at least 8KB whatever the board or header says.
+    if let Some(prg_ram) = mapper.borrow_mut().prg_ram() {
+        let mut size = match rom.metadata.is_nes2 {
+            true => rom.metadata.prg_ram_size + rom.metadata.prg_nvram_size,
+            false => prg_ram.len(),
+        };
+        if rom.trainer.is_some() {
+            size = size.max(DEFAULT_PRG_RAM_SIZE);
+        }
+        *prg_ram = PrgRam::new(size, rom.metadata.has_battery);
+        if let Some(trainer) = &rom.trainer {
+            prg_ram.load_at(TRAINER_ADDR - 0x6000, trainer);
+        }
+    }
+    Ok(mapper)
+}
+
+#[cfg(test)]
+mod mapper_tests {
+    use super::*;
+    use crate::rom::{checksum::Checksums, RomMetadata};
+
+    fn test_rom(mapper: u16) -> Rom {
+        Rom {
+            prg_rom: vec![0; 0x4000],
+            chr_rom: vec![0; 0x2000],
+            mapper,
+            submapper: 0,
+            screen_mirroring: Mirroring::Vertical,
+            metadata: RomMetadata::default(),
+            trainer: None,
+            checksums: Checksums::default(),
+            original_header: None,
+        }
+    }
+
+    #[test]
+    pub fn factory_builds_supported_mappers() {
+        let mapper = from_rom(test_rom(0)).unwrap();
+        assert!(mapper.borrow().mirroring() == Mirroring::Vertical);
+        for mapper in SUPPORTED_MAPPERS {
+            let mut rom = test_rom(*mapper);
+            rom.prg_rom = vec![0; 0x8000];
+            assert!(from_rom(rom).is_ok(), "mapper {mapper}");
+        }
+        assert!(!is_supported(255));
+    }
+
+    #[test]
+    pub fn factory_sizes_chr_ram_from_header() {
+        let mut rom = test_rom(0);
+        rom.chr_rom = vec![];
+        rom.metadata.chr_ram_size = 0x4000;
+        let mapper = from_rom(rom).unwrap();
+        mapper.borrow_mut().ppu_write(0x1FFF, 0x42).unwrap();
+        assert_eq!(mapper.borrow_mut().ppu_read(0x1FFF), 0x42);
+
+        // CHR ROM stays read only
+        let mapper = from_rom(test_rom(0)).unwrap();
+        assert!(mapper.borrow_mut().ppu_write(0x0000, 0x42).is_err());
+    }
+
+    #[test]
+    pub fn factory_loads_trainer() {
+        let mut rom = test_rom(0);
+        rom.trainer = Some(vec![0x42; 512]);
+        let mapper = from_rom(rom).unwrap();
+        assert_eq!(mapper.borrow_mut().cpu_read(0x6FFF), Some(0));
+        assert_eq!(mapper.borrow_mut().cpu_read(0x7000), Some(0x42));
+        assert_eq!(mapper.borrow_mut().cpu_read(0x71FF), Some(0x42));
+        assert_eq!(mapper.borrow_mut().cpu_read(0x7200), Some(0));
+        // The trainer isn't a change to save
+        assert!(mapper.borrow_mut().prg_ram().unwrap().take_dirty().is_none());
+
+        // Boards without work RAM, and NES 2.0 headers that say there is none, still get it for the trainer
+        for mapper in SUPPORTED_MAPPERS {
+            let mut rom = test_rom(*mapper);
+            rom.prg_rom = vec![0; 0x8000];
+            rom.metadata.is_nes2 = true;
+            rom.trainer = Some(vec![0x42; 512]);
+            let mapper = from_rom(rom).unwrap();
+            let mut mapper = mapper.borrow_mut();
+            assert_eq!(mapper.prg_ram().map(|prg_ram| prg_ram.len()), Some(DEFAULT_PRG_RAM_SIZE));
+            assert_eq!(mapper.prg_ram().unwrap().read(0x1000), 0x42);
+        }
+        let mut rom = test_rom(2);
+        rom.trainer = Some(vec![0x42; 512]);
+        assert_eq!(from_rom(rom).unwrap().borrow_mut().cpu_read(0x7000), Some(0x42));
+        assert_eq!(from_rom(test_rom(2)).unwrap().borrow_mut().cpu_read(0x7000), None);
+    }
+
+    #[test]
+    pub fn factory_sets_bus_conflicts_from_submapper() {
+        // $8000 holds 0, so a write only gets through without bus conflicts
+        let bank_after_write = |mapper: u16, submapper: u8| {
+            let mut rom = test_rom(mapper);
+            rom.prg_rom = vec![0xFF; 0x8000];
+            rom.prg_rom[0] = 0;
+            rom.chr_rom = (0..4).flat_map(|bank| vec![bank as u8; 0x2000]).collect();
+            rom.submapper = submapper;
+            let mapper = from_rom(rom).unwrap();
+            mapper.borrow_mut().cpu_write(0x8000, 3).unwrap();
+            let bank = mapper.borrow_mut().ppu_read(0);
+            bank
+        };
+        // CNROM defaults to bus conflicts, AxROM doesn't
+        assert_eq!(bank_after_write(3, 0), 0);
+        assert_eq!(bank_after_write(3, 1), 3);
+        let mirroring_after_write = |submapper: u8| {
+            let mut rom = test_rom(7);
+            rom.prg_rom = vec![0; 0x8000];
+            rom.submapper = submapper;
+            let mapper = from_rom(rom).unwrap();
+            mapper.borrow_mut().cpu_write(0x8000, 0b1_0000).unwrap();
+            let mirroring = mapper.borrow().mirroring();
+            mirroring
+        };
+        assert_eq!(mirroring_after_write(0), Mirroring::SingleScreenUpper);
+        assert_eq!(mirroring_after_write(2), Mirroring::SingleScreenLower);
+    }
+
+    #[test]
+    pub fn factory_rejects_partial_prg_banks() {
+        // NES 2.0 exponent-multiplier sizes can be smaller than a bank
+        for mapper in [1, 2] {
+            let mut rom = test_rom(mapper);
+            rom.prg_rom = vec![0; 3072];
+            assert!(matches!(from_rom(rom), Err(RomError::InvalidPrgSize { len: 3072, .. })));
+        }
+        let mut rom = test_rom(2);
+        rom.prg_rom = vec![0; 0x6000];
+        assert!(from_rom(rom).is_err());
+        let mut rom = test_rom(9);
+        rom.prg_rom = vec![0; 0x4000];
+        assert!(matches!(from_rom(rom), Err(RomError::InvalidPrgSize { mapper: 9, len: 0x4000 })));
+
+        // Every board takes its smallest size without panicking on reads
+        for mapper in SUPPORTED_MAPPERS {
+            let mut rom = test_rom(*mapper);
+            rom.prg_rom = vec![0; prg_size_requirements(*mapper).1];
+            let cartridge = from_rom(rom).unwrap();
+            for addr in (0x8000..=0xFFFF).step_by(0x1000) {
+                assert_eq!(cartridge.borrow_mut().cpu_read(addr), Some(0), "mapper {mapper}");
+            }
+        }
+    }
+
+    #[test]
+    pub fn factory_rejects_unknown_mappers() {
+        match from_rom(test_rom(255)) {
+            Ok(_) => panic!("Should have been an error"),
+            Err(error) => assert_eq!(error.to_string(), "Mapper 255 is not supported"),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use super::{ChrMemory, Mapper, PrgRam};
+use crate::{error::NesError, Mirroring};
+
+// Mapper 0. 16KB or 32KB of PRG ROM at $8000, a 16KB rom is mirrored into $C000. 8KB of CHR ROM, or CHR-RAM
+// when the rom has none. Only a few NROM boards have PRG-RAM, but test roms expect it at $6000.
+pub struct Nrom {
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    prg_ram: PrgRam,
+    mirroring: Mirroring,
+}
+
+impl Nrom {
+    pub fn new(prg_rom: Vec<u8>, chr: impl Into<ChrMemory>, mirroring: Mirroring) -> Self {
+        Nrom {
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: PrgRam::default(),
+            mirroring,
+        }
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        (addr - 0x8000) as usize % self.prg_rom.len()
+    }
+}
+
+impl Mapper for Nrom {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => Some(self.prg_ram.read((addr - 0x6000) as usize)),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        match addr {
+            0x6000..=0x7FFF if !self.prg_ram.is_empty() => {
+                self.prg_ram.write((addr - 0x6000) as usize, data);
+                Ok(())
+            },
+            // Tests load their programs straight into PRG ROM
+            0x8000..=0xFFFF if cfg!(test) => {
+                let index = self.prg_index(addr);
+                self.prg_rom[index] = data;
+                Ok(())
+            },
+            0x8000..=0xFFFF => Err(NesError::RomWrite { addr, data }),
+            _ => Err(NesError::UnmappedAccess { addr }),
+        }
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr.read(addr as usize)
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(addr as usize, addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring.clone()
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+#[cfg(test)]
+mod nrom_tests {
+    use super::*;
+
+    #[test]
+    pub fn sixteen_kb_prg_is_mirrored() {
+        let mut prg_rom = vec![0; 0x4000];
+        prg_rom[0x10] = 0xAB;
+        let mut nrom = Nrom::new(prg_rom, vec![0; 0x2000], Mirroring::Horizontal);
+        assert_eq!(nrom.cpu_read(0x8010), Some(0xAB));
+        assert_eq!(nrom.cpu_read(0xC010), Some(0xAB));
+        assert_eq!(nrom.cpu_read(0x5000), None);
+    }
+
+    #[test]
+    pub fn prg_ram() {
+        let mut nrom = Nrom::new(vec![0; 0x4000], vec![0; 0x2000], Mirroring::Horizontal);
+        nrom.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(nrom.cpu_read(0x6000), Some(0x42));
+
+        *nrom.prg_ram().unwrap() = PrgRam::new(0, false);
+        assert_eq!(nrom.cpu_read(0x6000), None);
+        assert!(nrom.cpu_write(0x6000, 0x42).is_err());
+    }
+
+    #[test]
+    pub fn chr_ram_is_writable() {
+        let mut nrom = Nrom::new(vec![0; 0x4000], vec![0; 0x2000], Mirroring::Horizontal);
+        assert_eq!(nrom.ppu_write(0x10, 0x11), Err(NesError::RomWrite { addr: 0x10, data: 0x11 }));
+
+        let mut nrom = Nrom::new(vec![0; 0x4000], vec![], Mirroring::Horizontal);
+        nrom.ppu_write(0x1FFF, 0x11).unwrap();
+        assert_eq!(nrom.ppu_read(0x1FFF), 0x11);
+    }
+}
@@ -0,0 +1,95 @@
+// Most boards with work RAM carry a single 8KB chip at $6000 - $7FFF
+pub const DEFAULT_PRG_RAM_SIZE: usize = 0x2000;
+
+// Work RAM on the cartridge. With a battery it keeps its contents while the console is off, so it gets
+// written out to a save file whenever it has changed.
+pub struct PrgRam {
+    data: Vec<u8>,
+    battery: bool,
+    // Set by writes, cleared once the contents are saved
+    dirty: bool,
+}
+
+impl Default for PrgRam {
+    fn default() -> Self {
+        PrgRam::new(DEFAULT_PRG_RAM_SIZE, false)
+    }
+}
+
+impl PrgRam {
+    pub fn new(size: usize, battery: bool) -> Self {
+        PrgRam { data: vec![0; size], battery, dirty: false }
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.battery
+    }
+
+    // Indexes past the end wrap around, so $6000 - $7FFF mirrors smaller chips
+    pub fn read(&self, index: usize) -> u8 {
+        self.data[index % self.data.len()]
+    }
+
+    pub fn write(&mut self, index: usize, data: u8) {
+        let len = self.data.len();
+        self.data[index % len] = data;
+        self.dirty = true;
+    }
+
+    // Restores the contents from a save file. Files of the wrong size are copied as far as they go.
+    pub fn load(&mut self, saved: &[u8]) {
+        let len = saved.len().min(self.data.len());
+        self.data[..len].copy_from_slice(&saved[..len]);
+        self.dirty = false;
+    }
+
+    // Copies data in at index without marking the RAM dirty, for contents the cartridge starts with
+    pub fn load_at(&mut self, index: usize, data: &[u8]) {
+        for (offset, byte) in data.iter().enumerate() {
+            let len = self.data.len();
+            self.data[(index + offset) % len] = *byte;
+        }
+    }
+
+    // Hands out the contents if they changed since the last call
+    pub fn take_dirty(&mut self) -> Option<&[u8]> {
+        match std::mem::take(&mut self.dirty) {
+            true => Some(&self.data),
+            false => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod prg_ram_tests {
+    use super::*;
+
+    #[test]
+    pub fn writes_mark_ram_dirty() {
+        let mut ram = PrgRam::new(0x2000, true);
+        assert!(ram.take_dirty().is_none());
+        ram.write(0x2001, 0x42);
+        assert_eq!(ram.read(0x0001), 0x42);
+        assert_eq!(ram.take_dirty().map(|data| data[1]), Some(0x42));
+        assert!(ram.take_dirty().is_none());
+    }
+
+    #[test]
+    pub fn load_copies_what_fits() {
+        let mut ram = PrgRam::new(4, true);
+        ram.load(&[1, 2, 3, 4, 5]);
+        assert_eq!(ram.read(3), 4);
+        ram.load(&[9]);
+        assert_eq!(ram.read(0), 9);
+        assert_eq!(ram.read(1), 2);
+        assert!(ram.take_dirty().is_none());
+    }
+}
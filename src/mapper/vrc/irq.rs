@@ -0,0 +1,127 @@
+// CPU cycles between scanline clocks are counted in thirds: 341 dots / 3 dots per CPU cycle
+const PRESCALER_PERIOD: i16 = 341;
+
+/*
+ * The IRQ counter shared by VRC4, VRC6 and VRC7. It counts up from the latch and fires when it overflows
+ * past $FF. In scanline mode a prescaler divides the CPU clock down to roughly once per scanline, cycle
+ * mode clocks it on every CPU cycle.
+ *
+ * Control
+ *     BIT 0: Enable after acknowledge
+ *     BIT 1: Enable
+ *     BIT 2: Mode (0: scanline; 1: CPU cycle)
+ */
+pub struct VrcIrq {
+    pub latch: u8,
+    counter: u8,
+    prescaler: i16,
+    enable_after_ack: bool,
+    enabled: bool,
+    cycle_mode: bool,
+    pending: bool,
+}
+
+impl Default for VrcIrq {
+    fn default() -> Self {
+        VrcIrq {
+            latch: 0,
+            counter: 0,
+            prescaler: PRESCALER_PERIOD,
+            enable_after_ack: false,
+            enabled: false,
+            cycle_mode: false,
+            pending: false,
+        }
+    }
+}
+
+impl VrcIrq {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Writing the control register also acknowledges the IRQ, and reloads the counter if it enables it
+    pub fn write_control(&mut self, value: u8) {
+        self.enable_after_ack = value & 0b001 != 0;
+        self.enabled = value & 0b010 != 0;
+        self.cycle_mode = value & 0b100 != 0;
+        self.pending = false;
+        if self.enabled {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER_PERIOD;
+        }
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.pending = false;
+        self.enabled = self.enable_after_ack;
+    }
+
+    pub fn clock(&mut self, cycles: usize) {
+        if !self.enabled {
+            return;
+        }
+        for _ in 0..cycles {
+            if self.cycle_mode {
+                self.clock_counter();
+                continue;
+            }
+            self.prescaler -= 3;
+            if self.prescaler <= 0 {
+                self.prescaler += PRESCALER_PERIOD;
+                self.clock_counter();
+            }
+        }
+    }
+
+    fn clock_counter(&mut self) {
+        if self.counter == 0xFF {
+            self.counter = self.latch;
+            self.pending = true;
+        } else {
+            self.counter += 1;
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+}
+
+#[cfg(test)]
+mod irq_tests {
+    use super::*;
+
+    #[test]
+    pub fn cycle_mode_fires_on_overflow() {
+        let mut irq = VrcIrq::new();
+        irq.latch = 0xFD;
+        irq.write_control(0b110);
+        irq.clock(2);
+        assert!(!irq.is_pending());
+        irq.clock(1);
+        assert!(irq.is_pending());
+
+        // Acknowledge without the enable after acknowledge bit stops the counter
+        irq.acknowledge();
+        irq.clock(10);
+        assert!(!irq.is_pending());
+    }
+
+    #[test]
+    pub fn scanline_mode_divides_cpu_clock() {
+        let mut irq = VrcIrq::new();
+        irq.latch = 0xFF;
+        irq.write_control(0b011);
+        // 341 / 3 rounds up to 114 cycles for the first scanline
+        irq.clock(113);
+        assert!(!irq.is_pending());
+        irq.clock(1);
+        assert!(irq.is_pending());
+
+        // Enable after acknowledge keeps it running, a latch of $FF fires on every scanline
+        irq.acknowledge();
+        irq.clock(114);
+        assert!(irq.is_pending());
+    }
+}
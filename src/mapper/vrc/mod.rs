@@ -0,0 +1,100 @@
+mod irq;
+pub mod vrc2_4;
+pub mod vrc6;
+pub mod vrc7;
+
+pub use irq::VrcIrq;
+
+use crate::Mirroring;
+
+/*
+ * Konami boards connect different CPU address lines to the register select pins of the same chip, so a
+ * register at $x001 on one board lives at $x004 on another. Each pin is listed as a mask of the address
+ * lines feeding it. Unknown boards OR both candidate lines together, which works unless a game relies on
+ * mirrors of the registers.
+ */
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Wiring {
+    pub pin_0: u16,
+    pub pin_1: u16,
+}
+
+const A0: u16 = 1 << 0;
+const A1: u16 = 1 << 1;
+const A2: u16 = 1 << 2;
+const A3: u16 = 1 << 3;
+const A4: u16 = 1 << 4;
+const A6: u16 = 1 << 6;
+const A7: u16 = 1 << 7;
+
+impl Wiring {
+    pub fn for_board(mapper: u16, submapper: u8) -> Self {
+        let (pin_0, pin_1) = match (mapper, submapper) {
+            // VRC4a, VRC4c
+            (21, 1) => (A1, A2),
+            (21, 2) => (A6, A7),
+            (21, _) => (A1 | A6, A2 | A7),
+            // VRC2a
+            (22, _) => (A1, A0),
+            // VRC4f, VRC4e, VRC2b
+            (23, 1) | (23, 3) => (A0, A1),
+            (23, 2) => (A2, A3),
+            (23, _) => (A0 | A2, A1 | A3),
+            // VRC4b, VRC4d, VRC2c
+            (25, 1) | (25, 3) => (A1, A0),
+            (25, 2) => (A3, A2),
+            (25, _) => (A1 | A3, A0 | A2),
+            // VRC6b swaps the pins of VRC6a
+            (26, _) => (A1, A0),
+            // VRC7b, VRC7a. VRC7 only has one select pin, its registers sit at $x000 and $x010 or $x008
+            (85, 1) => (A3, 0),
+            (85, 2) => (A4, 0),
+            (85, _) => (A3 | A4, 0),
+            _ => (A0, A1),
+        };
+        Wiring { pin_0, pin_1 }
+    }
+
+    // Folds an address down to $x000 - $x003 depending on the select pins
+    pub fn register(&self, addr: u16) -> u16 {
+        let pin_0 = (addr & self.pin_0 != 0) as u16;
+        let pin_1 = (addr & self.pin_1 != 0) as u16;
+        (addr & 0xF000) | (pin_1 << 1) | pin_0
+    }
+}
+
+// The two bit mirroring control shared by VRC4, VRC6 and VRC7
+fn mirroring_from_bits(bits: u8) -> Mirroring {
+    match bits & 0b11 {
+        0 => Mirroring::Vertical,
+        1 => Mirroring::Horizontal,
+        2 => Mirroring::SingleScreenLower,
+        _ => Mirroring::SingleScreenUpper,
+    }
+}
+
+#[cfg(test)]
+mod vrc_tests {
+    use super::*;
+
+    #[test]
+    pub fn wiring_folds_registers() {
+        let vrc4a = Wiring::for_board(21, 1);
+        assert_eq!(vrc4a.register(0xB002), 0xB001);
+        assert_eq!(vrc4a.register(0xB004), 0xB002);
+
+        let vrc4d = Wiring::for_board(25, 2);
+        assert_eq!(vrc4d.register(0xF008), 0xF001);
+        assert_eq!(vrc4d.register(0xF004), 0xF002);
+
+        // Without a submapper both candidate lines count
+        let vrc4_unknown = Wiring::for_board(23, 0);
+        assert_eq!(vrc4_unknown.register(0x8001), 0x8001);
+        assert_eq!(vrc4_unknown.register(0x8004), 0x8001);
+        assert_eq!(vrc4_unknown.register(0x800C), 0x8003);
+
+        let vrc7 = Wiring::for_board(85, 0);
+        assert_eq!(vrc7.register(0xA010), 0xA001);
+        assert_eq!(vrc7.register(0xA008), 0xA001);
+    }
+}
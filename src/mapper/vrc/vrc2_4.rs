@@ -0,0 +1,241 @@
+use super::{mirroring_from_bits, VrcIrq, Wiring};
+use crate::{error::NesError, mapper::{ChrMemory, Mapper, PrgRam}, Mirroring};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+/*
+ * Mappers 21, 22, 23 and 25. VRC2 is the cut down version of VRC4 without the IRQ counter, the PRG swap
+ * mode and one screen mirroring. Registers below are after folding the address through the board wiring.
+ *
+ * $8000 PRG bank at $8000, or $C000 in swap mode
+ * $9000 Mirroring
+ * $9002 VRC4 only. BIT 0: PRG-RAM enable; BIT 1: PRG swap mode
+ * $A000 PRG bank at $A000
+ * $B000 - $E003 1KB CHR banks, each one split into a low nibble at an even register and a high nibble at the
+ *     next odd one. $B000/$B001 is bank 0, $B002/$B003 bank 1, $C000/$C001 bank 2 and so on.
+ * $F000 IRQ latch low nibble, $F001 IRQ latch high nibble, $F002 IRQ control, $F003 IRQ acknowledge
+ */
+pub struct Vrc2_4 {
+    wiring: Wiring,
+    is_vrc2: bool,
+    // VRC2a drops the low bit of every CHR bank number
+    chr_shift: u8,
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    prg_ram: PrgRam,
+
+    prg_banks: [u8; 2],
+    chr_banks: [u16; 8],
+    mirroring: Mirroring,
+    prg_mode: u8,
+    irq: VrcIrq,
+}
+
+impl Vrc2_4 {
+    pub fn new(mapper: u16, submapper: u8, prg_rom: Vec<u8>, chr: impl Into<ChrMemory>) -> Self {
+        Vrc2_4 {
+            wiring: Wiring::for_board(mapper, submapper),
+            is_vrc2: mapper == 22 || submapper == 3,
+            chr_shift: if mapper == 22 { 1 } else { 0 },
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: PrgRam::default(),
+            prg_banks: [0; 2],
+            chr_banks: [0; 8],
+            mirroring: Mirroring::Vertical,
+            prg_mode: 0,
+            irq: VrcIrq::new(),
+        }
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        let bank_count = self.prg_rom.len() / PRG_BANK_SIZE;
+        let swapped = self.prg_mode & 0b10 != 0;
+        let bank = match ((addr - 0x8000) as usize / PRG_BANK_SIZE, swapped) {
+            (0, false) | (2, true) => self.prg_banks[0] as usize,
+            (0, true) | (2, false) => bank_count - 2,
+            (1, _) => self.prg_banks[1] as usize,
+            _ => bank_count - 1,
+        };
+        (bank % bank_count) * PRG_BANK_SIZE + addr as usize % PRG_BANK_SIZE
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let bank = (self.chr_banks[addr as usize / CHR_BANK_SIZE] >> self.chr_shift) as usize;
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    // VRC2 has no enable bit, its RAM is always there
+    fn prg_ram_enabled(&self) -> bool {
+        self.is_vrc2 || self.prg_mode & 1 != 0
+    }
+
+    fn write_chr_nibble(&mut self, register: u16, data: u8) {
+        let bank = ((register >> 12) as usize - 0xB) * 2 + ((register >> 1) & 1) as usize;
+        let nibble = (data & 0x0F) as u16;
+        self.chr_banks[bank] = match register & 1 {
+            0 => (self.chr_banks[bank] & 0x1F0) | nibble,
+            _ => (self.chr_banks[bank] & 0x00F) | (nibble << 4),
+        };
+    }
+}
+
+impl Mapper for Vrc2_4 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => Some(self.prg_ram.read((addr - 0x6000) as usize)),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        if let 0x6000..=0x7FFF = addr {
+            // Writes to disabled PRG-RAM go nowhere
+            if self.prg_ram_enabled() {
+                self.prg_ram.write((addr - 0x6000) as usize, data);
+            }
+            return Ok(());
+        }
+
+        let register = self.wiring.register(addr);
+        match register {
+            0x8000..=0x8003 => self.prg_banks[0] = data & 0x1F,
+            0x9000..=0x9003 if self.is_vrc2 => {
+                self.mirroring = mirroring_from_bits(data & 1);
+            },
+            0x9000..=0x9001 => self.mirroring = mirroring_from_bits(data),
+            0x9002..=0x9003 => self.prg_mode = data,
+            0xA000..=0xA003 => self.prg_banks[1] = data & 0x1F,
+            0xB000..=0xEFFF => self.write_chr_nibble(register, data),
+            0xF000..=0xFFFF if self.is_vrc2 => (),
+            0xF000 => self.irq.latch = (self.irq.latch & 0xF0) | (data & 0x0F),
+            0xF001 => self.irq.latch = (self.irq.latch & 0x0F) | (data << 4),
+            0xF002 => self.irq.write_control(data),
+            0xF003 => self.irq.acknowledge(),
+            _ => return Err(NesError::UnmappedAccess { addr }),
+        }
+        Ok(())
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr.read(self.chr_index(addr))
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(self.chr_index(addr), addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring.clone()
+    }
+
+    fn cpu_clock(&mut self, cycles: usize) {
+        self.irq.clock(cycles);
+    }
+
+    fn irq_active(&self) -> bool {
+        self.irq.is_pending()
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+#[cfg(test)]
+mod vrc2_4_tests {
+    use super::*;
+
+    // Every 8KB PRG bank and 1KB CHR bank starts with its own bank number
+    fn test_vrc(mapper: u16, submapper: u8) -> Vrc2_4 {
+        let mut prg_rom = vec![0; 16 * PRG_BANK_SIZE];
+        for bank in 0..16 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut chr_rom = vec![0; 256 * CHR_BANK_SIZE];
+        for bank in 0..256 {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Vrc2_4::new(mapper, submapper, prg_rom, chr_rom)
+    }
+
+    #[test]
+    pub fn prg_banking_and_swap_mode() {
+        let mut vrc4 = test_vrc(21, 1);
+        vrc4.cpu_write(0x8000, 3).unwrap();
+        vrc4.cpu_write(0xA000, 5).unwrap();
+        assert_eq!(vrc4.cpu_read(0x8000), Some(3));
+        assert_eq!(vrc4.cpu_read(0xA000), Some(5));
+        assert_eq!(vrc4.cpu_read(0xC000), Some(14));
+        assert_eq!(vrc4.cpu_read(0xE000), Some(15));
+
+        // $9002 is $9004 on VRC4a
+        vrc4.cpu_write(0x9004, 0b10).unwrap();
+        assert_eq!(vrc4.cpu_read(0x8000), Some(14));
+        assert_eq!(vrc4.cpu_read(0xC000), Some(3));
+    }
+
+    #[test]
+    pub fn chr_banks_are_written_in_nibbles() {
+        let mut vrc4 = test_vrc(25, 1);
+        // VRC4b swaps the select pins, $C002 selects the high nibble of bank 2
+        vrc4.cpu_write(0xC000, 0x0A).unwrap();
+        vrc4.cpu_write(0xC002, 0x01).unwrap();
+        assert_eq!(vrc4.ppu_read(0x0800), 0x1A);
+
+        // VRC2a ignores the lowest bit of the bank
+        let mut vrc2 = test_vrc(22, 0);
+        vrc2.cpu_write(0xB000, 0x07).unwrap();
+        assert_eq!(vrc2.ppu_read(0x0000), 3);
+    }
+
+    #[test]
+    pub fn prg_ram_enable() {
+        let mut vrc4 = test_vrc(21, 1);
+        vrc4.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(vrc4.cpu_read(0x6000), None);
+        vrc4.cpu_write(0x9004, 0b01).unwrap();
+        vrc4.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(vrc4.cpu_read(0x6000), Some(0x42));
+        vrc4.cpu_write(0x9004, 0b00).unwrap();
+        assert_eq!(vrc4.cpu_read(0x6000), None);
+
+        let mut vrc2 = test_vrc(22, 0);
+        vrc2.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(vrc2.cpu_read(0x6000), Some(0x42));
+    }
+
+    #[test]
+    pub fn mirroring() {
+        let mut vrc4 = test_vrc(23, 1);
+        vrc4.cpu_write(0x9000, 3).unwrap();
+        assert!(vrc4.mirroring() == Mirroring::SingleScreenUpper);
+
+        let mut vrc2 = test_vrc(23, 3);
+        vrc2.cpu_write(0x9000, 3).unwrap();
+        assert!(vrc2.mirroring() == Mirroring::Horizontal);
+    }
+
+    #[test]
+    pub fn irq() {
+        let mut vrc4 = test_vrc(23, 2);
+        // VRC4e selects registers with A2 and A3
+        vrc4.cpu_write(0xF000, 0x0E).unwrap();
+        vrc4.cpu_write(0xF004, 0x0F).unwrap();
+        vrc4.cpu_write(0xF008, 0b110).unwrap();
+        vrc4.cpu_clock(1);
+        assert!(!vrc4.irq_active());
+        vrc4.cpu_clock(1);
+        assert!(vrc4.irq_active());
+        vrc4.cpu_write(0xF00C, 0).unwrap();
+        assert!(!vrc4.irq_active());
+
+        // VRC2 has no IRQ counter
+        let mut vrc2 = test_vrc(22, 0);
+        vrc2.cpu_write(0xF002, 0b110).unwrap();
+        vrc2.cpu_clock(1000);
+        assert!(!vrc2.irq_active());
+    }
+}
@@ -0,0 +1,377 @@
+use super::{mirroring_from_bits, VrcIrq, Wiring};
+use crate::{error::NesError, mapper::{ChrMemory, Mapper, PrgRam}, Mirroring};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+// Two pulses at volume 15 and the sawtooth accumulator at its peak of 31
+const MAX_AUDIO_OUTPUT: f32 = 61.0;
+
+/*
+ * Mappers 24 (VRC6a) and 26 (VRC6b), which differ only in the order of the two register select pins.
+ *
+ * $8000 16KB PRG bank at $8000
+ * $9000 - $9002 Pulse 1, $9003 audio frequency control
+ * $A000 - $A002 Pulse 2
+ * $B000 - $B002 Sawtooth
+ * $B003 Banking control
+ *     BIT 0-1: CHR mode (0: eight 1KB banks; 1: four 2KB banks; 2, 3: four 1KB banks then two 2KB banks)
+ *     BIT 2-3: Mirroring
+ *     BIT 7: PRG-RAM enable
+ * $C000 8KB PRG bank at $C000, the last 8KB bank is fixed at $E000
+ * $D000 - $E003 CHR registers R0 - R7
+ * $F000 IRQ latch, $F001 IRQ control, $F002 IRQ acknowledge
+ */
+pub struct Vrc6 {
+    wiring: Wiring,
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    prg_ram: PrgRam,
+
+    prg_bank_16k: u8,
+    prg_bank_8k: u8,
+    chr_registers: [u8; 8],
+    banking_control: u8,
+    irq: VrcIrq,
+
+    pulses: [Pulse; 2],
+    sawtooth: Sawtooth,
+    audio_halted: bool,
+    // Right shift applied to every channel's period by $9003
+    frequency_shift: u8,
+}
+
+impl Vrc6 {
+    pub fn new(mapper: u16, prg_rom: Vec<u8>, chr: impl Into<ChrMemory>) -> Self {
+        Vrc6 {
+            wiring: Wiring::for_board(mapper, 0),
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: PrgRam::default(),
+            prg_bank_16k: 0,
+            prg_bank_8k: 0,
+            chr_registers: [0; 8],
+            banking_control: 0,
+            irq: VrcIrq::new(),
+            pulses: [Pulse::default(), Pulse::default()],
+            sawtooth: Sawtooth::default(),
+            audio_halted: false,
+            frequency_shift: 0,
+        }
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        let bank_count = self.prg_rom.len() / PRG_BANK_SIZE;
+        let bank = match addr {
+            0x8000..=0xBFFF => (self.prg_bank_16k as usize * 2) + (addr as usize - 0x8000) / PRG_BANK_SIZE,
+            0xC000..=0xDFFF => self.prg_bank_8k as usize,
+            _ => bank_count - 1,
+        };
+        (bank % bank_count) * PRG_BANK_SIZE + addr as usize % PRG_BANK_SIZE
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let slot = addr as usize / CHR_BANK_SIZE;
+        // 2KB banks take their lowest bit from PPU A10
+        let two_k = |register: u8| ((register & 0xFE) | (slot as u8 & 1)) as usize;
+        let bank = match (self.banking_control & 0b11, slot) {
+            (0, _) => self.chr_registers[slot] as usize,
+            (1, _) => two_k(self.chr_registers[slot / 2]),
+            (_, 0..=3) => self.chr_registers[slot] as usize,
+            (_, _) => two_k(self.chr_registers[4 + (slot - 4) / 2]),
+        };
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.banking_control & 0b1000_0000 != 0
+    }
+
+    fn write_frequency_control(&mut self, data: u8) {
+        self.audio_halted = data & 0b001 != 0;
+        self.frequency_shift = match data & 0b110 {
+            0 => 0,
+            0b010 | 0b110 => 4,
+            _ => 8,
+        };
+    }
+}
+
+impl Mapper for Vrc6 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => Some(self.prg_ram.read((addr - 0x6000) as usize)),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        if let 0x6000..=0x7FFF = addr {
+            if self.prg_ram_enabled() {
+                self.prg_ram.write((addr - 0x6000) as usize, data);
+            }
+            return Ok(());
+        }
+
+        let register = self.wiring.register(addr);
+        match register {
+            0x8000..=0x8003 => self.prg_bank_16k = data & 0x0F,
+            0x9000..=0x9002 => self.pulses[0].write(register & 0b11, data),
+            0x9003 => self.write_frequency_control(data),
+            0xA000..=0xA002 => self.pulses[1].write(register & 0b11, data),
+            0xB000..=0xB002 => self.sawtooth.write(register & 0b11, data),
+            0xB003 => self.banking_control = data,
+            0xC000..=0xC003 => self.prg_bank_8k = data & 0x1F,
+            0xD000..=0xD003 => self.chr_registers[(register & 0b11) as usize] = data,
+            0xE000..=0xE003 => self.chr_registers[4 + (register & 0b11) as usize] = data,
+            0xF000 => self.irq.latch = data,
+            0xF001 => self.irq.write_control(data),
+            0xF002 => self.irq.acknowledge(),
+            _ => return Err(NesError::UnmappedAccess { addr }),
+        }
+        Ok(())
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr.read(self.chr_index(addr))
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(self.chr_index(addr), addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        mirroring_from_bits(self.banking_control >> 2)
+    }
+
+    fn cpu_clock(&mut self, cycles: usize) {
+        self.irq.clock(cycles);
+        if self.audio_halted {
+            return;
+        }
+        for _ in 0..cycles {
+            self.pulses[0].clock(self.frequency_shift);
+            self.pulses[1].clock(self.frequency_shift);
+            self.sawtooth.clock(self.frequency_shift);
+        }
+    }
+
+    fn irq_active(&self) -> bool {
+        self.irq.is_pending()
+    }
+
+    fn expansion_audio(&self) -> f32 {
+        let output = self.pulses[0].output() + self.pulses[1].output() + self.sawtooth.output();
+        output as f32 / MAX_AUDIO_OUTPUT
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+/*
+ * $x000 BIT 0-3: Volume; BIT 4-6: Duty; BIT 7: Ignore duty and output the volume constantly
+ * $x001 Period low 8 bits
+ * $x002 BIT 0-3: Period high 4 bits; BIT 7: Enable
+ */
+#[derive(Default)]
+struct Pulse {
+    control: u8,
+    period: u16,
+    enabled: bool,
+    divider: u16,
+    // Counts down from 15, the output is high while it is at or below the duty
+    step: u8,
+}
+
+impl Pulse {
+    fn write(&mut self, register: u16, data: u8) {
+        match register {
+            0 => self.control = data,
+            1 => self.period = (self.period & 0x0F00) | data as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | (((data & 0x0F) as u16) << 8);
+                self.enabled = data & 0b1000_0000 != 0;
+                if !self.enabled {
+                    self.step = 15;
+                }
+            },
+        }
+    }
+
+    fn clock(&mut self, frequency_shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.divider == 0 {
+            self.divider = self.period >> frequency_shift;
+            self.step = self.step.wrapping_sub(1) & 0x0F;
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    fn output(&self) -> u8 {
+        let duty = (self.control >> 4) & 0b111;
+        let constant = self.control & 0b1000_0000 != 0;
+        match self.enabled && (constant || self.step <= duty) {
+            true => self.control & 0x0F,
+            false => 0,
+        }
+    }
+}
+
+/*
+ * $B000 BIT 0-5: Accumulator rate
+ * $B001 Period low 8 bits
+ * $B002 BIT 0-3: Period high 4 bits; BIT 7: Enable
+ */
+#[derive(Default)]
+struct Sawtooth {
+    rate: u8,
+    period: u16,
+    enabled: bool,
+    divider: u16,
+    // The accumulator takes the rate on every other of 14 steps, then resets
+    step: u8,
+    accumulator: u8,
+}
+
+impl Sawtooth {
+    fn write(&mut self, register: u16, data: u8) {
+        match register {
+            0 => self.rate = data & 0b11_1111,
+            1 => self.period = (self.period & 0x0F00) | data as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | (((data & 0x0F) as u16) << 8);
+                self.enabled = data & 0b1000_0000 != 0;
+                if !self.enabled {
+                    self.step = 0;
+                    self.accumulator = 0;
+                }
+            },
+        }
+    }
+
+    fn clock(&mut self, frequency_shift: u8) {
+        if !self.enabled {
+            return;
+        }
+        if self.divider > 0 {
+            self.divider -= 1;
+            return;
+        }
+        self.divider = self.period >> frequency_shift;
+        self.step += 1;
+        if self.step == 14 {
+            self.step = 0;
+            self.accumulator = 0;
+        } else if self.step.is_multiple_of(2) {
+            self.accumulator = self.accumulator.wrapping_add(self.rate);
+        }
+    }
+
+    fn output(&self) -> u8 {
+        self.accumulator >> 3
+    }
+}
+
+#[cfg(test)]
+mod vrc6_tests {
+    use super::*;
+
+    // Every 8KB PRG bank and 1KB CHR bank starts with its own bank number
+    fn test_vrc6(mapper: u16) -> Vrc6 {
+        let mut prg_rom = vec![0; 16 * PRG_BANK_SIZE];
+        for bank in 0..16 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut chr_rom = vec![0; 32 * CHR_BANK_SIZE];
+        for bank in 0..32 {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Vrc6::new(mapper, prg_rom, chr_rom)
+    }
+
+    #[test]
+    pub fn prg_banking() {
+        let mut vrc6 = test_vrc6(24);
+        vrc6.cpu_write(0x8000, 2).unwrap();
+        vrc6.cpu_write(0xC000, 9).unwrap();
+        assert_eq!(vrc6.cpu_read(0x8000), Some(4));
+        assert_eq!(vrc6.cpu_read(0xA000), Some(5));
+        assert_eq!(vrc6.cpu_read(0xC000), Some(9));
+        assert_eq!(vrc6.cpu_read(0xE000), Some(15));
+    }
+
+    #[test]
+    pub fn chr_modes_and_mirroring() {
+        // VRC6b swaps A0 and A1, so $B003 is still $B003 but $D001 is $D002
+        let mut vrc6 = test_vrc6(26);
+        vrc6.cpu_write(0xD002, 7).unwrap();
+        vrc6.cpu_write(0xE000, 20).unwrap();
+        assert_eq!(vrc6.ppu_read(0x0400), 7);
+        assert_eq!(vrc6.ppu_read(0x1000), 20);
+
+        vrc6.cpu_write(0xB003, 0b0000_0110).unwrap();
+        assert_eq!(vrc6.ppu_read(0x1000), 20);
+        assert_eq!(vrc6.ppu_read(0x1400), 21);
+        assert!(vrc6.mirroring() == Mirroring::Horizontal);
+    }
+
+    #[test]
+    pub fn prg_ram_enable() {
+        let mut vrc6 = test_vrc6(24);
+        vrc6.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(vrc6.cpu_read(0x6000), None);
+        vrc6.cpu_write(0xB003, 0b1000_0000).unwrap();
+        vrc6.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(vrc6.cpu_read(0x6000), Some(0x42));
+    }
+
+    #[test]
+    pub fn pulse_follows_duty() {
+        let mut vrc6 = test_vrc6(24);
+        // Duty 7 of 16 at volume 15, period 0 so every cycle is a step
+        vrc6.cpu_write(0x9000, 0b0111_1111).unwrap();
+        vrc6.cpu_write(0x9002, 0b1000_0000).unwrap();
+        let mut high_steps = 0;
+        for _ in 0..16 {
+            vrc6.cpu_clock(1);
+            if vrc6.expansion_audio() > 0.0 {
+                high_steps += 1;
+            }
+        }
+        assert_eq!(high_steps, 8);
+
+        // Halting the audio freezes the channels
+        vrc6.cpu_write(0x9003, 1).unwrap();
+        let output = vrc6.expansion_audio();
+        vrc6.cpu_clock(5);
+        assert_eq!(vrc6.expansion_audio(), output);
+    }
+
+    #[test]
+    pub fn sawtooth_ramps_and_resets() {
+        let mut vrc6 = test_vrc6(24);
+        vrc6.cpu_write(0xB000, 8).unwrap();
+        vrc6.cpu_write(0xB002, 0b1000_0000).unwrap();
+        // Six additions of 8 over 13 steps, then back to 0 on the 14th
+        vrc6.cpu_clock(13);
+        assert_eq!(vrc6.sawtooth.output(), 48 >> 3);
+        vrc6.cpu_clock(1);
+        assert_eq!(vrc6.sawtooth.output(), 0);
+    }
+
+    #[test]
+    pub fn irq() {
+        let mut vrc6 = test_vrc6(24);
+        vrc6.cpu_write(0xF000, 0xFF).unwrap();
+        vrc6.cpu_write(0xF001, 0b110).unwrap();
+        vrc6.cpu_clock(1);
+        assert!(vrc6.irq_active());
+        vrc6.cpu_write(0xF002, 0).unwrap();
+        assert!(!vrc6.irq_active());
+    }
+}
@@ -0,0 +1,206 @@
+use super::{mirroring_from_bits, VrcIrq, Wiring};
+use crate::{error::NesError, mapper::{ChrMemory, Mapper, PrgRam}, Mirroring};
+
+const PRG_BANK_SIZE: usize = 0x2000;
+const CHR_BANK_SIZE: usize = 0x0400;
+
+/*
+ * Mapper 85. The single register select pin is A3 on VRC7b and A4 on VRC7a, so the second register of each
+ * pair sits at $x008 or $x010. The audio ports are decoded from A4 and A5 on both boards.
+ *
+ * $8000, $8001 8KB PRG banks at $8000 and $A000
+ * $9000 8KB PRG bank at $C000, the last 8KB bank is fixed at $E000
+ * $9010, $9030 FM audio register select and data
+ * $A000 - $D001 1KB CHR banks 0 - 7
+ * $E000 BIT 0-1: Mirroring; BIT 6: Silence audio; BIT 7: PRG-RAM enable
+ * $E001 IRQ latch, $F000 IRQ control, $F001 IRQ acknowledge
+ *
+ * The FM synthesiser isn't emulated yet. Writes to its registers are kept so it can pick them up later, but
+ * the chip stays silent.
+ */
+pub struct Vrc7 {
+    wiring: Wiring,
+    prg_rom: Vec<u8>,
+    chr: ChrMemory,
+    prg_ram: PrgRam,
+
+    prg_banks: [u8; 3],
+    chr_banks: [u8; 8],
+    control: u8,
+    irq: VrcIrq,
+
+    audio_register: u8,
+    audio_registers: [u8; 0x40],
+}
+
+impl Vrc7 {
+    pub fn new(submapper: u8, prg_rom: Vec<u8>, chr: impl Into<ChrMemory>) -> Self {
+        Vrc7 {
+            wiring: Wiring::for_board(85, submapper),
+            prg_rom,
+            chr: chr.into(),
+            prg_ram: PrgRam::default(),
+            prg_banks: [0; 3],
+            chr_banks: [0; 8],
+            control: 0,
+            irq: VrcIrq::new(),
+            audio_register: 0,
+            audio_registers: [0; 0x40],
+        }
+    }
+
+    fn prg_index(&self, addr: u16) -> usize {
+        let bank_count = self.prg_rom.len() / PRG_BANK_SIZE;
+        let bank = match (addr - 0x8000) as usize / PRG_BANK_SIZE {
+            slot @ 0..=2 => self.prg_banks[slot] as usize,
+            _ => bank_count - 1,
+        };
+        (bank % bank_count) * PRG_BANK_SIZE + addr as usize % PRG_BANK_SIZE
+    }
+
+    fn chr_index(&self, addr: u16) -> usize {
+        let bank = self.chr_banks[addr as usize / CHR_BANK_SIZE] as usize;
+        (bank * CHR_BANK_SIZE + addr as usize % CHR_BANK_SIZE) % self.chr.len()
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.control & 0b1000_0000 != 0
+    }
+}
+
+impl Mapper for Vrc7 {
+    fn cpu_read(&mut self, addr: u16) -> Option<u8> {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => Some(self.prg_ram.read((addr - 0x6000) as usize)),
+            0x8000..=0xFFFF => Some(self.prg_rom[self.prg_index(addr)]),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        if let 0x6000..=0x7FFF = addr {
+            if self.prg_ram_enabled() {
+                self.prg_ram.write((addr - 0x6000) as usize, data);
+            }
+            return Ok(());
+        }
+
+        // Checked before folding, on VRC7b $9010 would otherwise look like $9000
+        match addr & 0xF030 {
+            0x9010 => {
+                self.audio_register = data;
+                return Ok(());
+            },
+            0x9030 => {
+                self.audio_registers[(self.audio_register & 0x3F) as usize] = data;
+                return Ok(());
+            },
+            _ => (),
+        }
+
+        let register = self.wiring.register(addr);
+        match register {
+            0x8000 => self.prg_banks[0] = data & 0x3F,
+            0x8001 => self.prg_banks[1] = data & 0x3F,
+            0x9000 => self.prg_banks[2] = data & 0x3F,
+            0xA000..=0xD001 => {
+                let bank = ((register >> 12) as usize - 0xA) * 2 + (register & 1) as usize;
+                self.chr_banks[bank] = data;
+            },
+            0xE000 => self.control = data,
+            0xE001 => self.irq.latch = data,
+            0xF000 => self.irq.write_control(data),
+            0xF001 => self.irq.acknowledge(),
+            _ => return Err(NesError::UnmappedAccess { addr }),
+        }
+        Ok(())
+    }
+
+    fn ppu_read(&mut self, addr: u16) -> u8 {
+        self.chr.read(self.chr_index(addr))
+    }
+
+    fn ppu_write(&mut self, addr: u16, data: u8) -> Result<(), NesError> {
+        self.chr.write(self.chr_index(addr), addr, data)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        mirroring_from_bits(self.control)
+    }
+
+    fn cpu_clock(&mut self, cycles: usize) {
+        self.irq.clock(cycles);
+    }
+
+    fn irq_active(&self) -> bool {
+        self.irq.is_pending()
+    }
+
+    fn prg_ram(&mut self) -> Option<&mut PrgRam> {
+        Some(&mut self.prg_ram)
+    }
+}
+
+#[cfg(test)]
+mod vrc7_tests {
+    use super::*;
+
+    // Every 8KB PRG bank and 1KB CHR bank starts with its own bank number
+    fn test_vrc7(submapper: u8) -> Vrc7 {
+        let mut prg_rom = vec![0; 16 * PRG_BANK_SIZE];
+        for bank in 0..16 {
+            prg_rom[bank * PRG_BANK_SIZE] = bank as u8;
+        }
+        let mut chr_rom = vec![0; 32 * CHR_BANK_SIZE];
+        for bank in 0..32 {
+            chr_rom[bank * CHR_BANK_SIZE] = bank as u8;
+        }
+        Vrc7::new(submapper, prg_rom, chr_rom)
+    }
+
+    #[test]
+    pub fn banking_with_either_wiring() {
+        for (submapper, second) in [(1, 0x08), (2, 0x10)] {
+            let mut vrc7 = test_vrc7(submapper);
+            vrc7.cpu_write(0x8000, 3).unwrap();
+            vrc7.cpu_write(0x8000 | second, 4).unwrap();
+            vrc7.cpu_write(0x9000, 5).unwrap();
+            vrc7.cpu_write(0xD000 | second, 9).unwrap();
+            assert_eq!(vrc7.cpu_read(0x8000), Some(3));
+            assert_eq!(vrc7.cpu_read(0xA000), Some(4));
+            assert_eq!(vrc7.cpu_read(0xC000), Some(5));
+            assert_eq!(vrc7.cpu_read(0xE000), Some(15));
+            assert_eq!(vrc7.ppu_read(0x1C00), 9);
+        }
+    }
+
+    #[test]
+    pub fn control_and_irq() {
+        let mut vrc7 = test_vrc7(2);
+        vrc7.cpu_write(0xE000, 0b1000_0001).unwrap();
+        assert!(vrc7.mirroring() == Mirroring::Horizontal);
+        vrc7.cpu_write(0x6000, 0x42).unwrap();
+        assert_eq!(vrc7.cpu_read(0x6000), Some(0x42));
+
+        vrc7.cpu_write(0xE010, 0xFF).unwrap();
+        vrc7.cpu_write(0xF000, 0b110).unwrap();
+        vrc7.cpu_clock(1);
+        assert!(vrc7.irq_active());
+        vrc7.cpu_write(0xF010, 0).unwrap();
+        assert!(!vrc7.irq_active());
+    }
+
+    #[test]
+    pub fn audio_writes_are_kept() {
+        for submapper in [1, 2] {
+            let mut vrc7 = test_vrc7(submapper);
+            vrc7.cpu_write(0x9000, 5).unwrap();
+            vrc7.cpu_write(0x9010, 0x10).unwrap();
+            vrc7.cpu_write(0x9030, 0xAB).unwrap();
+            assert_eq!(vrc7.audio_registers[0x10], 0xAB);
+            // The audio ports don't touch the PRG bank at $9000
+            assert_eq!(vrc7.cpu_read(0xC000), Some(5));
+            assert_eq!(vrc7.expansion_audio(), 0.0);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+use super::PPU;
+
+// Tile data fetched 8 dots ahead of the pixel being drawn. Every 8 dots the next tile is loaded into the
+// low byte of the shift registers, and each dot they shift left so bit 15 (minus fine X) is the current pixel.
+pub struct BackgroundPipeline {
+    next_tile_id: u8,
+    next_tile_attribute: u8,
+    next_tile_lo: u8,
+    next_tile_hi: u8,
+    pattern_lo_shifter: u16,
+    pattern_hi_shifter: u16,
+    attribute_lo_shifter: u16,
+    attribute_hi_shifter: u16,
+}
+
+impl BackgroundPipeline {
+    pub fn new() -> Self {
+        BackgroundPipeline {
+            next_tile_id: 0,
+            next_tile_attribute: 0,
+            next_tile_lo: 0,
+            next_tile_hi: 0,
+            pattern_lo_shifter: 0,
+            pattern_hi_shifter: 0,
+            attribute_lo_shifter: 0,
+            attribute_hi_shifter: 0,
+        }
+    }
+
+    fn load_shifters(&mut self) {
+        let expand = |bit: u8| if bit != 0 { 0xFF } else { 0x00 };
+        self.pattern_lo_shifter = (self.pattern_lo_shifter & 0xFF00) | self.next_tile_lo as u16;
+        self.pattern_hi_shifter = (self.pattern_hi_shifter & 0xFF00) | self.next_tile_hi as u16;
+        self.attribute_lo_shifter = (self.attribute_lo_shifter & 0xFF00) | expand(self.next_tile_attribute & 0b01);
+        self.attribute_hi_shifter = (self.attribute_hi_shifter & 0xFF00) | expand(self.next_tile_attribute & 0b10);
+    }
+
+    fn shift(&mut self) {
+        self.pattern_lo_shifter <<= 1;
+        self.pattern_hi_shifter <<= 1;
+        self.attribute_lo_shifter <<= 1;
+        self.attribute_hi_shifter <<= 1;
+    }
+
+    // Returns the (palette, pixel) pair at the given fine X offset
+    fn pixel(&self, fine_x: u8) -> (u8, u8) {
+        let bit = 0x8000 >> fine_x;
+        let pick = |shifter: u16| (shifter & bit != 0) as u8;
+        let pixel = (pick(self.pattern_hi_shifter) << 1) | pick(self.pattern_lo_shifter);
+        let palette = (pick(self.attribute_hi_shifter) << 1) | pick(self.attribute_lo_shifter);
+        (palette, pixel)
+    }
+}
+
+impl PPU {
+    // Reads from the PPU address space as seen by the rendering pipeline
+    pub(super) fn read_for_render(&mut self, addr: u16) -> u8 {
+        self.track_a12(addr);
+        self.mapper.borrow_mut().ppu_address(addr);
+        match addr {
+            0..=0x1FFF => self.mapper.borrow_mut().ppu_read(addr),
+            _ => self.vram[self.mirror_vram_addr(addr) as usize],
+        }
+    }
+
+    // Runs the fetches for the current dot of a visible or pre-render scanline. Dots 2 - 257 fetch the tiles
+    // for the rest of this line and dots 321 - 337 prefetch the first two tiles of the next one.
+    pub(super) fn fetch_background(&mut self) {
+        if !self.mask_register.is_rendering_enabled() {
+            return;
+        }
+        let dot = self.dot;
+        if !(2..=257).contains(&dot) && !(321..=337).contains(&dot) {
+            return;
+        }
+
+        if self.mask_register.is_show_background() {
+            self.background.shift();
+        }
+        let v = self.loopy_register.v;
+        match (dot - 1) % 8 {
+            0 => {
+                self.background.load_shifters();
+                self.background.next_tile_id = self.read_for_render(0x2000 | (v & 0x0FFF));
+            },
+            2 => {
+                let addr = 0x23C0 | (v & 0x0C00) | ((v >> 4) & 0x38) | ((v >> 2) & 0x07);
+                // Each attribute byte covers 4x4 tiles, pick the 2x2 quadrant v is in
+                let shift = ((self.loopy_register.coarse_y() & 0b10) << 1) | (self.loopy_register.coarse_x() & 0b10);
+                self.background.next_tile_attribute = (self.read_for_render(addr) >> shift) & 0b11;
+            },
+            4 => {
+                let addr = self.background_tile_addr();
+                self.background.next_tile_lo = self.read_for_render(addr);
+            },
+            6 => {
+                let addr = self.background_tile_addr() + 8;
+                self.background.next_tile_hi = self.read_for_render(addr);
+            },
+            _ => (),
+        }
+    }
+
+    fn background_tile_addr(&self) -> u16 {
+        let bank: u16 = if self.control_register.is_background_pattern_addr() { 0x1000 } else { 0 };
+        bank + self.background.next_tile_id as u16 * 16 + self.loopy_register.fine_y()
+    }
+
+    // Returns the (palette, pixel) pair of the background at x on the current scanline
+    pub(super) fn background_pixel(&self, x: usize) -> (u8, u8) {
+        let show_pixel = self.mask_register.is_show_background()
+            && (x >= 8 || self.mask_register.is_show_background_leftmost());
+        match show_pixel {
+            true => self.background.pixel(self.loopy_register.x),
+            false => (0, 0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod background_tests {
+    use super::*;
+    use crate::Mirroring;
+    use crate::ppu::palette::SYSTEM_PALETTE;
+    use crate::ppu::timing::{DOTS_PER_SCANLINE, SCANLINES_PER_FRAME};
+
+    const DOTS_PER_FRAME: usize = DOTS_PER_SCANLINE as usize * SCANLINES_PER_FRAME as usize;
+
+    // Tile 1 is a solid block of color 1, tile 2 has only its leftmost column set to color 3
+    fn test_ppu() -> PPU {
+        let mut chr_rom = vec![0; 0x2000];
+        for row in 0..8 {
+            chr_rom[16 + row] = 0xFF;
+            chr_rom[32 + row] = 0x80;
+            chr_rom[32 + 8 + row] = 0x80;
+        }
+        let mut ppu = PPU::from_chr_rom(chr_rom, Mirroring::Horizontal);
+        ppu.palette_table[0] = 0x0F;
+        ppu.palette_table[1] = 0x16;
+        ppu.palette_table[3] = 0x2A;
+        ppu.palette_table[5] = 0x11;
+        ppu
+    }
+
+    fn render_frame(ppu: &mut PPU) {
+        // Let the pre-render line of the first frame fill the pipeline, then draw a full frame
+        ppu.tick(DOTS_PER_FRAME);
+        ppu.tick(DOTS_PER_FRAME);
+    }
+
+    #[test]
+    pub fn draws_nametable_tiles() {
+        let mut ppu = test_ppu();
+        ppu.vram[0] = 1;
+        ppu.vram[33] = 2;
+        ppu.write_to_mask(0b0000_1010);
+        render_frame(&mut ppu);
+
+        assert_eq!(ppu.frame_buffer.get_pixel(0, 0), SYSTEM_PALETTE[0x16]);
+        assert_eq!(ppu.frame_buffer.get_pixel(7, 7), SYSTEM_PALETTE[0x16]);
+        assert_eq!(ppu.frame_buffer.get_pixel(8, 0), SYSTEM_PALETTE[0x0F]);
+        assert_eq!(ppu.frame_buffer.get_pixel(8, 8), SYSTEM_PALETTE[0x2A]);
+        assert_eq!(ppu.frame_buffer.get_pixel(9, 8), SYSTEM_PALETTE[0x0F]);
+    }
+
+    #[test]
+    pub fn uses_attribute_palette() {
+        let mut ppu = test_ppu();
+        ppu.vram[2] = 1;
+        // Top right quadrant of the first attribute byte uses palette 1
+        ppu.vram[0x3C0] = 0b0000_0100;
+        ppu.write_to_mask(0b0000_1010);
+        render_frame(&mut ppu);
+
+        assert_eq!(ppu.frame_buffer.get_pixel(16, 0), SYSTEM_PALETTE[0x11]);
+    }
+
+    #[test]
+    pub fn clips_left_column() {
+        let mut ppu = test_ppu();
+        ppu.vram[0] = 1;
+        ppu.vram[1] = 1;
+        ppu.write_to_mask(0b0000_1000);
+        render_frame(&mut ppu);
+
+        assert_eq!(ppu.frame_buffer.get_pixel(7, 0), SYSTEM_PALETTE[0x0F]);
+        assert_eq!(ppu.frame_buffer.get_pixel(8, 0), SYSTEM_PALETTE[0x16]);
+    }
+
+    #[test]
+    pub fn applies_fine_x_scroll() {
+        let mut ppu = test_ppu();
+        ppu.vram[1] = 2;
+        ppu.write_to_mask(0b0000_1010);
+        ppu.write_to_scroll(3);
+        ppu.write_to_scroll(0);
+        render_frame(&mut ppu);
+
+        // The left column of tile 2 at x = 8 moves 3 pixels left
+        assert_eq!(ppu.frame_buffer.get_pixel(5, 0), SYSTEM_PALETTE[0x2A]);
+        assert_eq!(ppu.frame_buffer.get_pixel(8, 0), SYSTEM_PALETTE[0x0F]);
+    }
+}
@@ -114,8 +114,8 @@ impl ControlRegister {
 impl ControlRegister {
     pub fn get_vram_increment_size(&self) -> u8 {
         match self.is_vram_add_increment() {
-            true => 1,
-            false => 28
+            true => 32,
+            false => 1
         }
     }
 }
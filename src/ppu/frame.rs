@@ -0,0 +1,35 @@
+pub const SCREEN_WIDTH: usize = 256;
+pub const SCREEN_HEIGHT: usize = 240;
+
+// RGB24 pixels, row by row, ready to be copied into an SDL texture
+pub struct Frame {
+    pub data: Vec<u8>,
+}
+
+impl Default for Frame {
+    fn default() -> Self {
+        Frame {
+            data: vec![0; SCREEN_WIDTH * SCREEN_HEIGHT * 3],
+        }
+    }
+}
+
+impl Frame {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, rgb: (u8, u8, u8)) {
+        let base = (y * SCREEN_WIDTH + x) * 3;
+        if base + 2 < self.data.len() {
+            self.data[base] = rgb.0;
+            self.data[base + 1] = rgb.1;
+            self.data[base + 2] = rgb.2;
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        let base = (y * SCREEN_WIDTH + x) * 3;
+        (self.data[base], self.data[base + 1], self.data[base + 2])
+    }
+}
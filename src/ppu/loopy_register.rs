@@ -0,0 +1,228 @@
+// Bit literals below are grouped by field (fine Y, nametable, coarse Y, coarse X) rather than by nibble
+#![allow(clippy::unusual_byte_groupings)]
+
+/*
+ * The PPU's internal scroll and address registers, named after the loopy doc that first described them.
+ * v and t are 15 bit addresses laid out as follows:
+ *
+ * yyy NN YYYYY XXXXX
+ * ||| || ||||| +++++-- coarse X scroll
+ * ||| || +++++-------- coarse Y scroll
+ * ||| ++-------------- nametable select
+ * +++----------------- fine Y scroll
+ *
+ * v: Current VRAM address, used for PPUDATA accesses and for rendering
+ * t: Temporary VRAM address, the top left tile of the screen. Written by PPUCTRL, PPUSCROLL and PPUADDR
+ * x: Fine X scroll (3 bits)
+ * w: Write toggle shared by PPUSCROLL and PPUADDR, false when the next write is the first of a pair
+ */
+
+const COARSE_X: u16 = 0b000_00_00000_11111;
+const COARSE_Y: u16 = 0b000_00_11111_00000;
+const NAMETABLE_X: u16 = 0b000_01_00000_00000;
+const NAMETABLE_Y: u16 = 0b000_10_00000_00000;
+const NAMETABLE: u16 = NAMETABLE_X | NAMETABLE_Y;
+const FINE_Y: u16 = 0b111_00_00000_00000;
+
+const HORIZONTAL_BITS: u16 = COARSE_X | NAMETABLE_X;
+const VERTICAL_BITS: u16 = COARSE_Y | NAMETABLE_Y | FINE_Y;
+
+pub struct LoopyRegister {
+    pub v: u16,
+    pub t: u16,
+    pub x: u8,
+    pub w: bool,
+}
+
+// Register writes
+impl LoopyRegister {
+    pub fn new() -> Self {
+        LoopyRegister { v: 0, t: 0, x: 0, w: false }
+    }
+
+    // PPUCTRL: the two nametable select bits go to t
+    pub fn write_control(&mut self, value: u8) {
+        self.t = (self.t & !NAMETABLE) | (((value & 0b11) as u16) << 10);
+    }
+
+    // PPUSCROLL: the first write sets coarse and fine X, the second coarse and fine Y
+    pub fn write_scroll(&mut self, value: u8) {
+        match self.w {
+            false => {
+                self.t = (self.t & !COARSE_X) | (value >> 3) as u16;
+                self.x = value & 0b111;
+            },
+            true => {
+                self.t = (self.t & !(COARSE_Y | FINE_Y)) | (((value >> 3) as u16) << 5) | (((value & 0b111) as u16) << 12);
+            },
+        }
+        self.w = !self.w;
+    }
+
+    // PPUADDR: the first write sets the hi byte of t (bit 14 is cleared), the second sets the lo byte and copies t to v
+    pub fn write_addr(&mut self, value: u8) {
+        match self.w {
+            false => self.t = (self.t & 0x00FF) | (((value & 0b0011_1111) as u16) << 8),
+            true => {
+                self.t = (self.t & 0xFF00) | value as u16;
+                self.v = self.t;
+            },
+        }
+        self.w = !self.w;
+    }
+
+    // Reading PPUSTATUS resets the write toggle
+    pub fn reset_latch(&mut self) {
+        self.w = false;
+    }
+
+    // The address PPUDATA reads and writes go to, only the low 14 bits reach the PPU bus
+    pub fn get(&self) -> u16 {
+        self.v & 0x3FFF
+    }
+
+    // PPUDATA accesses outside of rendering increment v by 1 or 32
+    pub fn increment(&mut self, inc: u8) {
+        self.v = self.v.wrapping_add(inc as u16) & 0x7FFF;
+    }
+}
+
+// Rendering updates
+impl LoopyRegister {
+    pub fn coarse_x(&self) -> u16 {
+        self.v & COARSE_X
+    }
+
+    pub fn coarse_y(&self) -> u16 {
+        (self.v & COARSE_Y) >> 5
+    }
+
+    pub fn fine_y(&self) -> u16 {
+        (self.v & FINE_Y) >> 12
+    }
+
+    // Moves v to the next tile, switching horizontal nametable when wrapping past the 32nd column
+    pub fn increment_coarse_x(&mut self) {
+        if self.v & COARSE_X == 31 {
+            self.v &= !COARSE_X;
+            self.v ^= NAMETABLE_X;
+        } else {
+            self.v += 1;
+        }
+    }
+
+    // Moves v to the next pixel row. Coarse Y wraps at 30 rows into the next vertical nametable,
+    // rows 30 and 31 are attribute data and wrap back to 0 without switching nametables.
+    pub fn increment_y(&mut self) {
+        if self.v & FINE_Y != FINE_Y {
+            self.v += 0x1000;
+            return;
+        }
+
+        self.v &= !FINE_Y;
+        let coarse_y = match self.coarse_y() {
+            29 => {
+                self.v ^= NAMETABLE_Y;
+                0
+            },
+            31 => 0,
+            coarse_y => coarse_y + 1,
+        };
+        self.v = (self.v & !COARSE_Y) | (coarse_y << 5);
+    }
+
+    pub fn copy_horizontal(&mut self) {
+        self.v = (self.v & !HORIZONTAL_BITS) | (self.t & HORIZONTAL_BITS);
+    }
+
+    pub fn copy_vertical(&mut self) {
+        self.v = (self.v & !VERTICAL_BITS) | (self.t & VERTICAL_BITS);
+    }
+}
+
+#[cfg(test)]
+mod loopy_register_tests {
+    use super::*;
+
+    #[test]
+    pub fn write_addr_mirrors_down() {
+        let mut foo = LoopyRegister::new();
+        foo.write_addr(0xFF);
+        assert_eq!(foo.t, 0x3F00);
+        foo.write_addr(0x05);
+        assert_eq!(foo.get(), 0x3F05);
+        assert!(!foo.w);
+    }
+
+    #[test]
+    pub fn increment_wraps() {
+        let mut foo = LoopyRegister::new();
+        foo.v = 0x7FFD;
+        foo.increment(3);
+        assert_eq!(foo.v, 0x0000);
+    }
+
+    // Example sequence from the loopy doc
+    #[test]
+    pub fn scroll_and_addr_share_t() {
+        let mut foo = LoopyRegister::new();
+        foo.write_control(0b0000_0011);
+        assert_eq!(foo.t, 0b000_11_00000_00000);
+
+        foo.reset_latch();
+        foo.write_scroll(0b0111_1101);
+        assert_eq!(foo.t, 0b000_11_00000_01111);
+        assert_eq!(foo.x, 0b101);
+        assert!(foo.w);
+
+        foo.write_scroll(0b0101_1110);
+        assert_eq!(foo.t, 0b110_11_01011_01111);
+        assert!(!foo.w);
+
+        foo.write_addr(0b0011_1101);
+        assert_eq!(foo.t, 0b011_11_01011_01111);
+        foo.write_addr(0b1111_0000);
+        assert_eq!(foo.t, 0b011_11_01111_10000);
+        assert_eq!(foo.v, foo.t);
+    }
+
+    #[test]
+    pub fn increment_coarse_x_switches_nametable() {
+        let mut foo = LoopyRegister::new();
+        foo.v = 0b000_00_00000_11111;
+        foo.increment_coarse_x();
+        assert_eq!(foo.v, 0b000_01_00000_00000);
+        foo.increment_coarse_x();
+        assert_eq!(foo.v, 0b000_01_00000_00001);
+    }
+
+    #[test]
+    pub fn increment_y() {
+        let mut foo = LoopyRegister::new();
+        foo.v = 0b110_00_00011_00000;
+        foo.increment_y();
+        assert_eq!(foo.v, 0b111_00_00011_00000);
+        foo.increment_y();
+        assert_eq!(foo.v, 0b000_00_00100_00000);
+
+        // Row 29 wraps into the next vertical nametable
+        foo.v = 0b111_00_11101_00000;
+        foo.increment_y();
+        assert_eq!(foo.v, 0b000_10_00000_00000);
+
+        // Row 31 wraps without switching nametables
+        foo.v = 0b111_10_11111_00000;
+        foo.increment_y();
+        assert_eq!(foo.v, 0b000_10_00000_00000);
+    }
+
+    #[test]
+    pub fn copy_from_t() {
+        let mut foo = LoopyRegister::new();
+        foo.t = 0b101_11_10101_01010;
+        foo.copy_horizontal();
+        assert_eq!(foo.v, 0b000_01_00000_01010);
+        foo.copy_vertical();
+        assert_eq!(foo.v, foo.t);
+    }
+}
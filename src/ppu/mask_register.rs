@@ -0,0 +1,84 @@
+pub struct MaskRegister(u8);
+
+/*
+ * BIT 0: Greyscale (0: normal color, 1: produce a greyscale display)
+ * BIT 1: Show background in the leftmost 8 pixels of the screen (0: hide, 1: show)
+ * BIT 2: Show sprites in the leftmost 8 pixels of the screen (0: hide, 1: show)
+ * BIT 3: Show background
+ * BIT 4: Show sprites
+ * BIT 5: Emphasize red (green on PAL/Dendy)
+ * BIT 6: Emphasize green (red on PAL/Dendy)
+ * BIT 7: Emphasize blue
+ */
+
+// Constructor and Getters
+impl MaskRegister {
+    pub fn new() -> Self {
+        MaskRegister(0b0)
+    }
+
+    pub fn is_greyscale(&self) -> bool {
+        self.0 & 0b0000_0001 != 0
+    }
+
+    pub fn is_show_background_leftmost(&self) -> bool {
+        self.0 & 0b0000_0010 != 0
+    }
+
+    pub fn is_show_sprites_leftmost(&self) -> bool {
+        self.0 & 0b0000_0100 != 0
+    }
+
+    pub fn is_show_background(&self) -> bool {
+        self.0 & 0b0000_1000 != 0
+    }
+
+    pub fn is_show_sprites(&self) -> bool {
+        self.0 & 0b0001_0000 != 0
+    }
+
+    pub fn is_emphasize_red(&self) -> bool {
+        self.0 & 0b0010_0000 != 0
+    }
+
+    pub fn is_emphasize_green(&self) -> bool {
+        self.0 & 0b0100_0000 != 0
+    }
+
+    pub fn is_emphasize_blue(&self) -> bool {
+        self.0 & 0b1000_0000 != 0
+    }
+
+    pub fn update(&mut self, value: u8) {
+        self.0 = value;
+    }
+}
+
+// Not getters
+impl MaskRegister {
+    pub fn is_rendering_enabled(&self) -> bool {
+        self.is_show_background() || self.is_show_sprites()
+    }
+}
+
+#[cfg(test)]
+mod mask_register_tests {
+    use super::*;
+
+    #[test]
+    pub fn getter_tests() {
+        let mut foo = MaskRegister::new();
+        assert!(!foo.is_rendering_enabled());
+
+        foo.update(0b1010_1010);
+        assert!(!foo.is_greyscale());
+        assert!(foo.is_show_background_leftmost());
+        assert!(!foo.is_show_sprites_leftmost());
+        assert!(foo.is_show_background());
+        assert!(!foo.is_show_sprites());
+        assert!(foo.is_emphasize_red());
+        assert!(!foo.is_emphasize_green());
+        assert!(foo.is_emphasize_blue());
+        assert!(foo.is_rendering_enabled());
+    }
+}
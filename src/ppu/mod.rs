@@ -1,92 +1,252 @@
-mod addr_register;
+mod background;
 mod control_register;
+pub mod frame;
+mod loopy_register;
+mod mask_register;
+pub mod palette;
+mod render;
+mod sprites;
+mod status_register;
+pub mod timing;
 
-use addr_register::AddrRegister;
+use background::BackgroundPipeline;
 use control_register::ControlRegister;
+use frame::Frame;
+use loopy_register::LoopyRegister;
+use mask_register::MaskRegister;
+use sprites::SpriteSlot;
+use status_register::StatusRegister;
 
-use crate::{rom::Rom, Mirroring};
+use crate::{error::NesError, mapper::SharedMapper, Mirroring};
 
 pub struct PPU {
-    pub chr_rom: Vec<u8>,
+    // Pattern tables and nametable mirroring come from the cartridge
+    mapper: SharedMapper,
     pub palette_table: [u8; 32],
     pub vram: [u8; 2048],
     pub oam_data: [u8; 256],
-    pub mirroring: Mirroring,
-    pub addr_register: AddrRegister,
+    pub oam_addr: u8,
     pub control_register: ControlRegister,
+    pub mask_register: MaskRegister,
+    pub status_register: StatusRegister,
+    // Internal v, t, x and w registers shared by PPUCTRL, PPUSCROLL, PPUADDR and rendering
+    pub loopy_register: LoopyRegister,
     internal_data_buffer: u8,
+    background: BackgroundPipeline,
+    // Sprites picked by evaluation on the previous scanline, drawn on this one
+    sprites: Vec<SpriteSlot>,
+    pub frame_buffer: Frame,
+
+    // Position of the next dot to render, see timing.rs
+    pub scanline: u16,
+    pub dot: u16,
+    pub frame: u64,
+    // Dots since power up, and the state of A12 on the PPU address bus, for mappers counting A12 rises
+    cycles: u64,
+    a12_high: bool,
+    a12_low_since: u64,
+    // Set when the PPU pulls the NMI line low, taken by the bus and forwarded to the CPU
+    nmi_pending: bool,
+}
+
+// $3F10, $3F14, $3F18 and $3F1C mirror the backdrop entries at $3F00, $3F04, $3F08 and $3F0C,
+// and the 32 palette bytes repeat through $3FFF
+pub fn palette_index(addr: u16) -> usize {
+    let index = (addr & 0x1F) as usize;
+    match index {
+        0x10 | 0x14 | 0x18 | 0x1C => index - 0x10,
+        _ => index,
+    }
 }
 
 impl PPU {
-    pub fn from_rom(rom: &Rom) -> Self {
+    pub fn new(mapper: SharedMapper) -> Self {
         PPU {
-            chr_rom: rom.chr_rom.clone(),
-            mirroring: rom.screen_mirroring.clone(),
+            mapper,
             vram: [0; 2048],
             oam_data: [0; 256],
+            oam_addr: 0,
             palette_table: [0; 32],
-            addr_register: AddrRegister::new(),
             control_register: ControlRegister::new(),
+            mask_register: MaskRegister::new(),
+            status_register: StatusRegister::new(),
+            loopy_register: LoopyRegister::new(),
             internal_data_buffer: 0,
+            background: BackgroundPipeline::new(),
+            sprites: Vec::with_capacity(sprites::MAX_SPRITES_PER_LINE),
+            frame_buffer: Frame::new(),
+            scanline: 0,
+            dot: 0,
+            frame: 0,
+            cycles: 0,
+            a12_high: false,
+            a12_low_since: 0,
+            nmi_pending: false,
         }
     }
 
+    // Builds a PPU on top of an NROM cart with the given pattern tables
+    #[cfg(test)]
+    pub fn from_chr_rom(chr_rom: Vec<u8>, mirroring: Mirroring) -> Self {
+        let nrom = crate::mapper::nrom::Nrom::new(vec![0; 0x4000], chr_rom, mirroring);
+        PPU::new(crate::mapper::shared(nrom))
+    }
+
+    // Outside of rendering v sits on the PPU address bus, so setting it can toggle A12
     pub fn write_to_ppu_addr(&mut self, addr: u8) {
-        self.addr_register.update(addr);
+        self.loopy_register.write_addr(addr);
+        if !self.loopy_register.w {
+            self.track_a12(self.loopy_register.get());
+        }
+    }
+
+    pub fn write_to_scroll(&mut self, value: u8) {
+        self.loopy_register.write_scroll(value);
+    }
+
+    pub fn write_to_mask(&mut self, value: u8) {
+        self.mask_register.update(value);
+    }
+
+    // Reading PPUSTATUS clears the vblank flag and resets the shared write toggle
+    pub fn read_status(&mut self) -> u8 {
+        let data = self.status_register.0;
+        self.status_register.set_vblank(false);
+        self.loopy_register.reset_latch();
+        data
+    }
+
+    pub fn write_to_oam_addr(&mut self, value: u8) {
+        self.oam_addr = value;
+    }
+
+    // Writes to OAMDATA increment OAMADDR, reads do not
+    pub fn write_to_oam_data(&mut self, value: u8) {
+        self.oam_data[self.oam_addr as usize] = value;
+        self.oam_addr = self.oam_addr.wrapping_add(1);
     }
 
-    pub fn write_to_ppu_data(&mut self, data: u8) {
-        let mut addr = self.addr_register.get();
-        addr = self.mirror_vram_addr(addr);
-        self.vram[addr as usize] = data;
+    pub fn read_oam_data(&self) -> u8 {
+        self.oam_data[self.oam_addr as usize]
+    }
+
+    pub fn write_to_ppu_data(&mut self, data: u8) -> Result<(), NesError> {
+        let addr = self.loopy_register.get();
+        self.increment_vram_addr();
+        self.track_a12(addr);
+        self.mapper.borrow_mut().ppu_address(addr);
+
+        match addr {
+            0..=0x1FFF => self.mapper.borrow_mut().ppu_write(addr, data)?,
+            0x2000..=0x3EFF => {
+                let mirrored_addr = self.mirror_vram_addr(addr);
+                self.vram[mirrored_addr as usize] = data;
+            },
+            _ => self.palette_table[palette_index(addr)] = data & 0x3F,
+        }
+        Ok(())
     }
 
+    // Enabling NMI while the vblank flag is still set fires an NMI straight away
     pub fn write_to_control_register(&mut self, value: u8) {
+        let nmi_was_enabled = self.control_register.is_generate_nmi();
         self.control_register.update(value);
+        self.loopy_register.write_control(value);
+        if !nmi_was_enabled && self.control_register.is_generate_nmi() && self.status_register.is_vblank() {
+            self.nmi_pending = true;
+        }
+    }
+
+    // Tells the mapper when A12 rises and how long it was low before that
+    pub(super) fn track_a12(&mut self, addr: u16) {
+        let a12_high = addr & 0x1000 != 0;
+        match (self.a12_high, a12_high) {
+            (false, true) => self.mapper.borrow_mut().ppu_a12_rise(self.cycles - self.a12_low_since),
+            (true, false) => self.a12_low_since = self.cycles,
+            _ => (),
+        }
+        self.a12_high = a12_high;
     }
 
     fn increment_vram_addr(&mut self) {
         let increment_amount = self.control_register.get_vram_increment_size();
-        self.addr_register.increment(increment_amount);
+        self.loopy_register.increment(increment_amount);
+    }
+
+    // Advances v the way the rendering pipeline does at the given scanline and dot. Coarse X moves every
+    // 8 dots while fetching tiles, Y moves at dot 256, the horizontal bits are reloaded from t at dot 257
+    // and the pre-render line reloads the vertical bits during dots 280 - 304.
+    pub fn update_scroll(&mut self, scanline: u16, dot: u16) {
+        if !self.mask_register.is_rendering_enabled() {
+            return;
+        }
+        let pre_render_line = scanline == 261;
+        if scanline >= 240 && !pre_render_line {
+            return;
+        }
+
+        match dot {
+            256 => {
+                self.loopy_register.increment_coarse_x();
+                self.loopy_register.increment_y();
+            },
+            257 => self.loopy_register.copy_horizontal(),
+            280..=304 if pre_render_line => self.loopy_register.copy_vertical(),
+            1..=255 | 328 | 336 if dot.is_multiple_of(8) => self.loopy_register.increment_coarse_x(),
+            _ => (),
+        }
     }
 
+    // Reads below the palettes go through a one byte buffer, so the data shows up one read late. Palette reads
+    // are returned straight away but still refill the buffer with the nametable byte mirrored underneath them.
     pub fn read_data(&mut self) -> u8 {
-        let addr = self.addr_register.get();
+        let addr = self.loopy_register.get();
         self.increment_vram_addr();
+        self.track_a12(addr);
+        self.mapper.borrow_mut().ppu_address(addr);
 
+        let result = self.internal_data_buffer;
         match addr {
             0..=0x1FFF => {
-                let result = self.internal_data_buffer;
-                self.internal_data_buffer = self.chr_rom[addr as usize];
+                self.internal_data_buffer = self.mapper.borrow_mut().ppu_read(addr);
                 result
             },
-            0x2000..=0x2FFF => {
-                let result = self.internal_data_buffer;
+            0x2000..=0x3EFF => {
                 let mirrored_addr = self.mirror_vram_addr(addr);
                 self.internal_data_buffer = self.vram[mirrored_addr as usize];
                 result
             },
-            0x3000..=0x3EFF => panic!("0x3000 - 0x3eff addresses are not expected to be used, requested {addr:04X}"),
-            0x3F00..=0x3FFF => self.palette_table[(addr - 0x3F00) as usize],
-            _ => panic!("Unexpected read to a mirrored addressed {addr:04X}")
+            _ => {
+                let mirrored_addr = self.mirror_vram_addr(addr - 0x1000);
+                self.internal_data_buffer = self.vram[mirrored_addr as usize];
+                self.palette_table[palette_index(addr)]
+            },
         }
     }
 
     pub fn mirror_vram_addr(&self, addr: u16) -> u16 {
         let normalized_addr = addr & 0x2FFF; // Mirros down 0x3000 - 0x3FFF addr to 0x2000 - 0x2FFF range
+        let mirroring = self.mapper.borrow().mirroring();
+
+        // Single screen boards wire both nametable address lines to the same physical page
+        match mirroring {
+            Mirroring::SingleScreenLower => return normalized_addr & 0x3FF,
+            Mirroring::SingleScreenUpper => return 0x400 | (normalized_addr & 0x3FF),
+            _ => (),
+        }
 
         match normalized_addr {
-            0x2000..0x2400 => addr - 0x2000,
+            0x2000..0x2400 => normalized_addr - 0x2000,
             0x2400..0x2800 => {
-                let offset = if self.mirroring == Mirroring::Horizontal { 0 } else { 0x400 };
-                addr - 0x2400 + offset
+                let offset = if mirroring == Mirroring::Horizontal { 0 } else { 0x400 };
+                normalized_addr - 0x2400 + offset
             },
             0x2800..0x2C00 => {
-                let offset = if self.mirroring == Mirroring::Vertical { 0 } else { 0x400 };
-                addr - 0x2800 + offset
+                let offset = if mirroring == Mirroring::Vertical { 0 } else { 0x400 };
+                normalized_addr - 0x2800 + offset
             },
             0x2C00..0x3000 => {
-                addr - 0x2C00 + 0x400
+                normalized_addr - 0x2C00 + 0x400
             },
             _ => panic!("{addr:04X} cannot be mirrored onto VRAM")
         }
@@ -94,34 +254,215 @@ impl PPU {
 }
 
 #[cfg(test)]
+#[allow(clippy::unusual_byte_groupings)]
 mod ppu_tests {
     use super::*;
 
     fn test_ppu() -> PPU {
-        PPU {
-            chr_rom: vec![0; 2048],
-            mirroring: Mirroring::Horizontal,
-            vram: [0; 2048],
-            oam_data: [0; 256],
-            palette_table: [0; 32],
-            addr_register: AddrRegister::new(),
-            control_register: ControlRegister::new(),
-            internal_data_buffer: 0,
-        }
+        PPU::from_chr_rom(vec![0; 2048], Mirroring::Horizontal)
     }
 
     #[test]
     pub fn mirror_vram_addr_test() {
-        let mut ppu = test_ppu();
+        let ppu = test_ppu();
         assert_eq!(ppu.mirror_vram_addr(0x2212), 0x212);
         assert_eq!(ppu.mirror_vram_addr(0x2612), 0x212);
         assert_eq!(ppu.mirror_vram_addr(0x2AAB), 0x6AB);
         assert_eq!(ppu.mirror_vram_addr(0x2EAC), 0x6AC);
 
-        ppu.mirroring = Mirroring::Vertical;
+        let ppu = PPU::from_chr_rom(vec![0; 2048], Mirroring::Vertical);
         assert_eq!(ppu.mirror_vram_addr(0x2212), 0x212);
         assert_eq!(ppu.mirror_vram_addr(0x2612), 0x612);
         assert_eq!(ppu.mirror_vram_addr(0x2BAB), 0x3AB);
         assert_eq!(ppu.mirror_vram_addr(0x2EAC), 0x6AC);
+
+        let ppu = PPU::from_chr_rom(vec![0; 2048], Mirroring::SingleScreenUpper);
+        assert_eq!(ppu.mirror_vram_addr(0x2212), 0x612);
+        assert_eq!(ppu.mirror_vram_addr(0x2EAC), 0x6AC);
+    }
+
+    #[test]
+    pub fn read_status_clears_vblank_and_write_toggle() {
+        let mut ppu = test_ppu();
+        ppu.status_register.set_vblank(true);
+        ppu.status_register.set_sprite_zero_hit(true);
+        ppu.write_to_ppu_addr(0x21);
+
+        assert_eq!(ppu.read_status(), 0b1100_0000);
+        assert_eq!(ppu.read_status(), 0b0100_0000);
+
+        // The next PPUADDR write is treated as the hi byte again
+        ppu.write_to_ppu_addr(0x23);
+        ppu.write_to_ppu_addr(0x05);
+        assert_eq!(ppu.loopy_register.get(), 0x2305);
+    }
+
+    #[test]
+    pub fn scroll_and_addr_share_write_toggle() {
+        let mut ppu = test_ppu();
+        ppu.write_to_scroll(0x10);
+        ppu.write_to_ppu_addr(0x08);
+        // The PPUADDR write was taken as the second write of the pair and only replaced the lo byte
+        assert_eq!(ppu.loopy_register.t, 0x0008);
+        assert_eq!(ppu.loopy_register.get(), 0x0008);
+    }
+
+    #[test]
+    pub fn oam_data_increments_on_write() {
+        let mut ppu = test_ppu();
+        ppu.write_to_oam_addr(0xFF);
+        ppu.write_to_oam_data(0x11);
+        ppu.write_to_oam_data(0x22);
+        assert_eq!(ppu.oam_data[0xFF], 0x11);
+        assert_eq!(ppu.oam_data[0x00], 0x22);
+
+        ppu.write_to_oam_addr(0xFF);
+        assert_eq!(ppu.read_oam_data(), 0x11);
+        assert_eq!(ppu.read_oam_data(), 0x11);
+    }
+
+    #[test]
+    pub fn update_scroll_over_a_scanline() {
+        let mut ppu = test_ppu();
+        ppu.write_to_mask(0b0000_1000);
+        ppu.loopy_register.t = 0b000_01_00000_00011;
+        ppu.loopy_register.v = 0b000_00_00100_00011;
+
+        for dot in 1..=340 {
+            ppu.update_scroll(10, dot);
+        }
+        // Fine Y moved one row and the horizontal bits were reloaded from t then moved 2 tiles for the next line
+        assert_eq!(ppu.loopy_register.v, 0b001_01_00100_00101);
+
+        // Nothing moves while rendering is off
+        ppu.write_to_mask(0);
+        ppu.update_scroll(10, 8);
+        assert_eq!(ppu.loopy_register.v, 0b001_01_00100_00101);
+    }
+
+    #[test]
+    pub fn pre_render_line_copies_vertical_bits() {
+        let mut ppu = test_ppu();
+        ppu.write_to_mask(0b0001_0000);
+        ppu.loopy_register.t = 0b011_10_00111_00000;
+        ppu.loopy_register.v = 0b000_00_11000_00000;
+        ppu.update_scroll(261, 280);
+        assert_eq!(ppu.loopy_register.v, 0b011_10_00111_00000);
+
+        // Visible lines do not
+        ppu.loopy_register.v = 0;
+        ppu.update_scroll(100, 280);
+        assert_eq!(ppu.loopy_register.v, 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn ppu_data_write_increments_address() {
+        let mut ppu = test_ppu();
+        ppu.write_to_ppu_addr(0x23);
+        ppu.write_to_ppu_addr(0x05);
+        ppu.write_to_ppu_data(0x66).unwrap();
+        ppu.write_to_ppu_data(0x77).unwrap();
+        assert_eq!(ppu.vram[0x305], 0x66);
+        assert_eq!(ppu.vram[0x306], 0x77);
+
+        ppu.write_to_control_register(0b0000_0100);
+        ppu.write_to_ppu_data(0x88).unwrap();
+        ppu.write_to_ppu_data(0x99).unwrap();
+        assert_eq!(ppu.vram[0x307], 0x88);
+        assert_eq!(ppu.vram[0x327], 0x99);
+    }
+
+    #[test]
+    pub fn ppu_data_reads_are_buffered() {
+        let mut ppu = test_ppu();
+        ppu.vram[0x305] = 0x66;
+        ppu.vram[0x306] = 0x77;
+        ppu.write_to_ppu_addr(0x23);
+        ppu.write_to_ppu_addr(0x05);
+        ppu.read_data();
+        assert_eq!(ppu.read_data(), 0x66);
+        assert_eq!(ppu.read_data(), 0x77);
+
+        // $3000 - $3EFF mirrors the nametables
+        ppu.write_to_ppu_addr(0x33);
+        ppu.write_to_ppu_addr(0x05);
+        ppu.read_data();
+        assert_eq!(ppu.read_data(), 0x66);
+    }
+
+    #[test]
+    pub fn palette_writes_and_mirrors() {
+        let mut ppu = test_ppu();
+        ppu.write_to_ppu_addr(0x3F);
+        ppu.write_to_ppu_addr(0x10);
+        ppu.write_to_ppu_data(0x21).unwrap();
+        assert_eq!(ppu.palette_table[0x00], 0x21);
+
+        ppu.write_to_ppu_addr(0x3F);
+        ppu.write_to_ppu_addr(0x25);
+        ppu.write_to_ppu_data(0x12).unwrap();
+        assert_eq!(ppu.palette_table[0x05], 0x12);
+
+        assert_eq!(palette_index(0x3F14), 0x04);
+        assert_eq!(palette_index(0x3F11), 0x11);
+        assert_eq!(palette_index(0x3FFC), 0x0C);
+    }
+
+    #[test]
+    pub fn palette_reads_are_immediate_and_refill_buffer() {
+        let mut ppu = test_ppu();
+        ppu.palette_table[0x05] = 0x12;
+        ppu.vram[0x705] = 0x99;
+        ppu.write_to_ppu_addr(0x3F);
+        ppu.write_to_ppu_addr(0x05);
+        assert_eq!(ppu.read_data(), 0x12);
+
+        // The buffer now holds the nametable byte under the palette, $2F05
+        ppu.write_to_ppu_addr(0x20);
+        ppu.write_to_ppu_addr(0x00);
+        assert_eq!(ppu.read_data(), 0x99);
+    }
+
+    #[test]
+    pub fn pattern_table_writes() {
+        let mut ppu = test_ppu();
+        ppu.write_to_ppu_addr(0x00);
+        ppu.write_to_ppu_addr(0x10);
+        assert_eq!(ppu.write_to_ppu_data(0x11), Err(NesError::RomWrite { addr: 0x0010, data: 0x11 }));
+
+        let mut ppu = PPU::from_chr_rom(vec![], Mirroring::Vertical);
+        ppu.write_to_ppu_addr(0x00);
+        ppu.write_to_ppu_addr(0x10);
+        ppu.write_to_ppu_data(0x11).unwrap();
+        ppu.write_to_ppu_addr(0x00);
+        ppu.write_to_ppu_addr(0x10);
+        ppu.read_data();
+        assert_eq!(ppu.read_data(), 0x11);
+    }
+
+    // Backgrounds at $0000 and sprites at $1000 raise A12 once per line, when the sprite patterns are fetched
+    #[test]
+    pub fn sprite_fetches_clock_mmc3_counter() {
+        use crate::mapper::{mmc3::Mmc3, shared};
+        use timing::{DOTS_PER_SCANLINE, SCANLINES_PER_FRAME};
+
+        let mapper = shared(Mmc3::new(0, vec![0; 0x8000], vec![0; 0x2000], Mirroring::Vertical));
+        let mut ppu = PPU::new(mapper.clone());
+        ppu.write_to_control_register(0b0000_1000);
+        ppu.write_to_mask(0b0001_1000);
+        ppu.tick(DOTS_PER_SCANLINE as usize * SCANLINES_PER_FRAME as usize);
+
+        let mut mmc3 = mapper.borrow_mut();
+        mmc3.cpu_write(0xC000, 10).unwrap();
+        mmc3.cpu_write(0xC001, 0).unwrap();
+        mmc3.cpu_write(0xE000, 0).unwrap();
+        mmc3.cpu_write(0xE001, 0).unwrap();
+        drop(mmc3);
+
+        // Line 0 reloads the counter, line 10 takes it to 0
+        ppu.tick(DOTS_PER_SCANLINE as usize * 10 + 257);
+        assert!(!mapper.borrow().irq_active());
+        ppu.tick(1);
+        assert!(mapper.borrow().irq_active());
+    }
+}
@@ -0,0 +1,103 @@
+use super::frame::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use super::palette::SYSTEM_PALETTE;
+use super::PPU;
+
+impl PPU {
+    // Combines the background and sprite pixels for the current dot and writes the result into the frame buffer
+    pub(super) fn draw_pixel(&mut self) {
+        let x = (self.dot - 1) as usize;
+        let y = self.scanline as usize;
+        if x >= SCREEN_WIDTH || y >= SCREEN_HEIGHT {
+            return;
+        }
+
+        let (background_palette, background_pixel) = self.background_pixel(x);
+        let (palette, pixel) = match self.sprite_pixel(x) {
+            Some(sprite) => {
+                // Sprite 0 hit never triggers on the last column
+                if sprite.is_sprite_zero && background_pixel != 0 && x != 255 {
+                    self.status_register.set_sprite_zero_hit(true);
+                }
+                match sprite.behind_background && background_pixel != 0 {
+                    true => (background_palette, background_pixel),
+                    false => (sprite.palette, sprite.pixel),
+                }
+            },
+            None => (background_palette, background_pixel),
+        };
+
+        // Pixel 0 of every palette shows the universal backdrop color
+        let palette_index = match pixel {
+            0 => 0,
+            _ => (palette << 2) | pixel,
+        };
+        let mut color = self.palette_table[palette_index as usize] & 0x3F;
+        if self.mask_register.is_greyscale() {
+            color &= 0x30;
+        }
+        self.frame_buffer.set_pixel(x, y, SYSTEM_PALETTE[color as usize]);
+    }
+}
+
+#[cfg(test)]
+mod render_tests {
+    use super::*;
+    use crate::Mirroring;
+    use crate::ppu::timing::{DOTS_PER_SCANLINE, SCANLINES_PER_FRAME};
+
+    const DOTS_PER_FRAME: usize = DOTS_PER_SCANLINE as usize * SCANLINES_PER_FRAME as usize;
+
+    // Tile 1 is solid color 1, tile 2 is solid color 2
+    fn test_ppu() -> PPU {
+        let mut chr_rom = vec![0; 0x2000];
+        for row in 0..8 {
+            chr_rom[16 + row] = 0xFF;
+            chr_rom[32 + 8 + row] = 0xFF;
+        }
+        let mut ppu = PPU::from_chr_rom(chr_rom, Mirroring::Horizontal);
+        ppu.palette_table[0] = 0x0F;
+        ppu.palette_table[1] = 0x16;
+        ppu.palette_table[0x12] = 0x2A;
+        ppu.oam_data = [0xFF; 256];
+        ppu.write_to_mask(0b0001_1110);
+        ppu
+    }
+
+    #[test]
+    pub fn sprite_priority() {
+        let mut ppu = test_ppu();
+        ppu.vram[0] = 1;
+        ppu.vram[1] = 1;
+        // Sprites are drawn one line below their Y coordinate
+        ppu.oam_data[0..8].copy_from_slice(&[0, 2, 0b0000_0000, 0, 0, 2, 0b0010_0000, 12]);
+        // The first frame starts without the pre-render line filling the background pipeline
+        ppu.tick(DOTS_PER_FRAME);
+        ppu.tick(DOTS_PER_FRAME);
+
+        assert_eq!(ppu.frame_buffer.get_pixel(0, 0), SYSTEM_PALETTE[0x16]);
+        assert_eq!(ppu.frame_buffer.get_pixel(0, 1), SYSTEM_PALETTE[0x2A]);
+        // The second sprite is behind the background, which is only opaque in the first two tiles
+        assert_eq!(ppu.frame_buffer.get_pixel(13, 1), SYSTEM_PALETTE[0x16]);
+        assert_eq!(ppu.frame_buffer.get_pixel(17, 1), SYSTEM_PALETTE[0x2A]);
+    }
+
+    #[test]
+    pub fn sprite_zero_hit() {
+        let mut ppu = test_ppu();
+        ppu.oam_data[0..4].copy_from_slice(&[20, 2, 0, 100]);
+        ppu.tick(DOTS_PER_FRAME);
+        // No opaque background under sprite 0
+        assert!(!ppu.status_register.is_sprite_zero_hit());
+
+        ppu.vram[3 * 32 + 12] = 1;
+        // Stop before the pre-render line clears the flag
+        ppu.tick(DOTS_PER_SCANLINE as usize * 30);
+        assert!(ppu.status_register.is_sprite_zero_hit());
+
+        ppu.tick(DOTS_PER_FRAME);
+        assert!(ppu.status_register.is_sprite_zero_hit());
+        ppu.write_to_mask(0b0000_1000);
+        ppu.tick(DOTS_PER_FRAME);
+        assert!(!ppu.status_register.is_sprite_zero_hit());
+    }
+}
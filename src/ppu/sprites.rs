@@ -0,0 +1,244 @@
+use super::PPU;
+
+pub const MAX_SPRITES_PER_LINE: usize = 8;
+
+// A sprite picked by evaluation for the next scanline, with its pattern row already fetched and flipped
+pub struct SpriteSlot {
+    x: u8,
+    attributes: u8,
+    pattern_lo: u8,
+    pattern_hi: u8,
+    is_sprite_zero: bool,
+}
+
+// An opaque sprite pixel, see sprite_pixel
+pub struct SpritePixel {
+    pub palette: u8,
+    pub pixel: u8,
+    pub behind_background: bool,
+    pub is_sprite_zero: bool,
+}
+
+/*
+ * OAM holds 64 sprites of 4 bytes each
+ * BYTE 0: Y position of the top of the sprite minus one
+ * BYTE 1: Tile index. In 8x16 mode bit 0 selects the pattern table and the rest is the top tile
+ * BYTE 2: Attributes
+ *     BIT 0-1: Palette (4 to 7) of the sprite
+ *     BIT 5: Priority (0: in front of background; 1: behind background)
+ *     BIT 6: Flip horizontally
+ *     BIT 7: Flip vertically
+ * BYTE 3: X position of the left side of the sprite
+ */
+impl PPU {
+    fn sprite_height(&self) -> u16 {
+        if self.control_register.is_sprite_size() { 16 } else { 8 }
+    }
+
+    fn sprite_row(&self, scanline: u16, y: u8) -> Option<u16> {
+        let row = scanline.wrapping_sub(y as u16);
+        if row < self.sprite_height() { Some(row) } else { None }
+    }
+
+    // Picks the first 8 sprites in OAM order that cover the next scanline and fetches their pattern rows.
+    // Sprites are drawn one line below their Y coordinate, so the rows are measured from this scanline.
+    pub(super) fn evaluate_sprites(&mut self) {
+        self.sprites.clear();
+        let scanline = self.scanline;
+        let mut n = 0;
+        while n < 64 && self.sprites.len() < MAX_SPRITES_PER_LINE {
+            let entry = &self.oam_data[n * 4..n * 4 + 4];
+            if let Some(row) = self.sprite_row(scanline, entry[0]) {
+                let slot = self.fetch_sprite(entry[1], entry[2], entry[3], row, n == 0);
+                self.sprites.push(slot);
+            }
+            n += 1;
+        }
+
+        // After 8 sprites are found the hardware keeps scanning for overflow but increments both the sprite
+        // and the byte index on a miss, so it compares tile indexes, attributes and X positions as Y coordinates.
+        let mut m = 0;
+        while n < 64 {
+            if self.sprite_row(scanline, self.oam_data[n * 4 + m]).is_some() {
+                self.status_register.set_sprite_overflow(true);
+                break;
+            }
+            n += 1;
+            m = (m + 1) & 0b11;
+        }
+    }
+
+    fn fetch_sprite(&mut self, tile: u8, attributes: u8, x: u8, row: u16, is_sprite_zero: bool) -> SpriteSlot {
+        let height = self.sprite_height();
+        let row = if attributes & 0b1000_0000 != 0 { height - 1 - row } else { row };
+        let (bank, tile) = match height {
+            16 => ((tile as u16 & 1) * 0x1000, (tile & 0xFE) as u16 + row / 8),
+            _ => {
+                let bank = if self.control_register.is_sprite_pattern_addr() { 0x1000 } else { 0 };
+                (bank, tile as u16)
+            },
+        };
+        let addr = bank + tile * 16 + row % 8;
+        let mut pattern_lo = self.read_for_render(addr);
+        let mut pattern_hi = self.read_for_render(addr + 8);
+        if attributes & 0b0100_0000 != 0 {
+            pattern_lo = pattern_lo.reverse_bits();
+            pattern_hi = pattern_hi.reverse_bits();
+        }
+
+        SpriteSlot { x, attributes, pattern_lo, pattern_hi, is_sprite_zero }
+    }
+
+    // Slots left empty by evaluation, and every slot on the pre-render line, still fetch tile $FF.
+    // Nothing is drawn from them but mappers watching A12 see the fetches.
+    pub(super) fn fetch_unused_sprite_slots(&mut self) {
+        for _ in self.sprites.len()..MAX_SPRITES_PER_LINE {
+            self.fetch_sprite(0xFF, 0, 0xFF, 0, false);
+        }
+    }
+
+    // Returns the first opaque sprite pixel at x on the current scanline, earlier OAM entries win
+    pub(super) fn sprite_pixel(&self, x: usize) -> Option<SpritePixel> {
+        if !self.mask_register.is_show_sprites() || (x < 8 && !self.mask_register.is_show_sprites_leftmost()) {
+            return None;
+        }
+
+        self.sprites.iter().find_map(|sprite| {
+            let column = x.checked_sub(sprite.x as usize).filter(|column| *column < 8)?;
+            let bit = 0x80 >> column;
+            let pixel = (((sprite.pattern_hi & bit != 0) as u8) << 1) | (sprite.pattern_lo & bit != 0) as u8;
+            match pixel {
+                0 => None,
+                _ => Some(SpritePixel {
+                    palette: 4 + (sprite.attributes & 0b11),
+                    pixel,
+                    behind_background: sprite.attributes & 0b0010_0000 != 0,
+                    is_sprite_zero: sprite.is_sprite_zero,
+                }),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod sprites_tests {
+    use super::*;
+    use crate::Mirroring;
+
+    // Tile 1 is solid color 1, tile 2 only has its top left pixel set, tile 3 is solid color 2
+    fn test_ppu() -> PPU {
+        let mut chr_rom = vec![0; 0x2000];
+        for row in 0..8 {
+            chr_rom[16 + row] = 0xFF;
+            chr_rom[48 + 8 + row] = 0xFF;
+        }
+        chr_rom[32] = 0x80;
+        let mut ppu = PPU::from_chr_rom(chr_rom, Mirroring::Horizontal);
+        ppu.write_to_mask(0b0001_0110);
+        ppu
+    }
+
+    fn set_sprite(ppu: &mut PPU, index: usize, sprite: [u8; 4]) {
+        ppu.oam_data[index * 4..index * 4 + 4].copy_from_slice(&sprite);
+    }
+
+    #[test]
+    pub fn evaluation_picks_first_eight() {
+        let mut ppu = test_ppu();
+        ppu.oam_data = [0xFF; 256];
+        for index in 0..10 {
+            set_sprite(&mut ppu, index, [10, 1, 0, index as u8 * 8]);
+        }
+        ppu.scanline = 12;
+        ppu.evaluate_sprites();
+        assert_eq!(ppu.sprites.len(), 8);
+        assert!(ppu.sprites[0].is_sprite_zero);
+        assert_eq!(ppu.sprites[7].x, 56);
+        assert!(ppu.status_register.is_sprite_overflow());
+
+        ppu.status_register.set_sprite_overflow(false);
+        ppu.scanline = 18;
+        ppu.evaluate_sprites();
+        assert!(ppu.sprites.is_empty());
+        assert!(!ppu.status_register.is_sprite_overflow());
+    }
+
+    #[test]
+    pub fn overflow_scan_is_buggy() {
+        let mut ppu = test_ppu();
+        ppu.oam_data = [0xFF; 256];
+        for index in 0..8 {
+            set_sprite(&mut ppu, index, [10, 1, 0, 0]);
+        }
+        // The 9th sprite is on the line but the scan reads its Y correctly only for the first extra sprite
+        set_sprite(&mut ppu, 9, [10, 1, 0, 0]);
+        ppu.scanline = 10;
+        ppu.evaluate_sprites();
+        // Sprite 8 misses, so sprite 9 is checked on its tile byte (1) instead of its Y
+        assert!(!ppu.status_register.is_sprite_overflow());
+
+        // A tile index that happens to be in range triggers a false overflow
+        set_sprite(&mut ppu, 9, [0xFF, 5, 0, 0]);
+        ppu.evaluate_sprites();
+        assert!(ppu.status_register.is_sprite_overflow());
+    }
+
+    #[test]
+    pub fn sprite_pixel_with_flips() {
+        let mut ppu = test_ppu();
+        ppu.oam_data = [0xFF; 256];
+        set_sprite(&mut ppu, 0, [0, 2, 0b0000_0001, 16]);
+        set_sprite(&mut ppu, 1, [0, 2, 0b1100_0010, 32]);
+        ppu.scanline = 0;
+        ppu.evaluate_sprites();
+
+        let pixel = ppu.sprite_pixel(16).unwrap();
+        assert_eq!((pixel.palette, pixel.pixel), (5, 1));
+        assert!(pixel.is_sprite_zero);
+        assert!(ppu.sprite_pixel(17).is_none());
+        // The flipped sprite has its set pixel in the bottom right corner
+        assert!(ppu.sprite_pixel(39).is_none());
+
+        ppu.scanline = 7;
+        ppu.evaluate_sprites();
+        let pixel = ppu.sprite_pixel(39).unwrap();
+        assert_eq!((pixel.palette, pixel.pixel, pixel.is_sprite_zero), (6, 1, false));
+        assert!(ppu.sprite_pixel(16).is_none());
+    }
+
+    #[test]
+    pub fn tall_sprites() {
+        let mut ppu = test_ppu();
+        ppu.oam_data = [0xFF; 256];
+        ppu.write_to_control_register(0b0010_0000);
+        // Odd tile indexes come from the 0x1000 pattern table, this one uses tiles 2 and 3 of the first table
+        set_sprite(&mut ppu, 0, [0, 2, 0, 0]);
+        ppu.scanline = 0;
+        ppu.evaluate_sprites();
+        assert_eq!(ppu.sprite_pixel(0).unwrap().pixel, 1);
+        ppu.scanline = 8;
+        ppu.evaluate_sprites();
+        assert_eq!(ppu.sprite_pixel(0).unwrap().pixel, 2);
+        ppu.scanline = 16;
+        ppu.evaluate_sprites();
+        assert!(ppu.sprite_pixel(0).is_none());
+
+        // Vertical flip covers the whole 16 rows
+        set_sprite(&mut ppu, 0, [0, 2, 0b1000_0000, 0]);
+        ppu.scanline = 0;
+        ppu.evaluate_sprites();
+        assert_eq!(ppu.sprite_pixel(3).unwrap().pixel, 2);
+    }
+
+    #[test]
+    pub fn left_column_clipping() {
+        let mut ppu = test_ppu();
+        ppu.write_to_mask(0b0001_0000);
+        ppu.oam_data = [0xFF; 256];
+        set_sprite(&mut ppu, 0, [0, 1, 0, 4]);
+        ppu.scanline = 0;
+        ppu.evaluate_sprites();
+        assert!(ppu.sprite_pixel(7).is_none());
+        assert!(ppu.sprite_pixel(8).is_some());
+    }
+}
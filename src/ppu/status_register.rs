@@ -0,0 +1,70 @@
+pub struct StatusRegister(pub u8);
+
+/*
+ * BIT 0-4: Open bus, returns whatever was last driven onto the PPU data bus
+ * BIT 5: Sprite overflow, set when more than 8 sprites are found on a scanline
+ * BIT 6: Sprite 0 hit, set when an opaque pixel of sprite 0 overlaps an opaque background pixel
+ * BIT 7: Vertical blank has started (0: not in vblank; 1: in vblank)
+ */
+
+// Constructor and Getters/Setters
+impl StatusRegister {
+    pub fn new() -> Self {
+        StatusRegister(0b0)
+    }
+
+    pub fn set_sprite_overflow(&mut self, val: bool) {
+        match val {
+            true => self.0 |= 0b0010_0000,
+            false => self.0 &= 0b1101_1111,
+        }
+    }
+
+    pub fn is_sprite_overflow(&self) -> bool {
+        self.0 & 0b0010_0000 != 0
+    }
+
+    pub fn set_sprite_zero_hit(&mut self, val: bool) {
+        match val {
+            true => self.0 |= 0b0100_0000,
+            false => self.0 &= 0b1011_1111,
+        }
+    }
+
+    pub fn is_sprite_zero_hit(&self) -> bool {
+        self.0 & 0b0100_0000 != 0
+    }
+
+    pub fn set_vblank(&mut self, val: bool) {
+        match val {
+            true => self.0 |= 0b1000_0000,
+            false => self.0 &= 0b0111_1111,
+        }
+    }
+
+    pub fn is_vblank(&self) -> bool {
+        self.0 & 0b1000_0000 != 0
+    }
+}
+
+#[cfg(test)]
+mod status_register_tests {
+    use super::*;
+
+    #[test]
+    pub fn getter_setter_tests() {
+        let mut foo = StatusRegister::new();
+        foo.set_sprite_overflow(true);
+        foo.set_sprite_zero_hit(true);
+        foo.set_vblank(true);
+        assert_eq!(foo.0, 0b1110_0000);
+        assert!(foo.is_sprite_overflow());
+        assert!(foo.is_sprite_zero_hit());
+        assert!(foo.is_vblank());
+
+        foo.set_sprite_overflow(false);
+        foo.set_sprite_zero_hit(false);
+        foo.set_vblank(false);
+        assert_eq!(foo.0, 0b0);
+    }
+}
@@ -0,0 +1,156 @@
+use super::PPU;
+
+pub const DOTS_PER_SCANLINE: u16 = 341;
+pub const SCANLINES_PER_FRAME: u16 = 262;
+pub const VISIBLE_SCANLINES: u16 = 240;
+pub const VBLANK_SCANLINE: u16 = 241;
+pub const PRE_RENDER_SCANLINE: u16 = 261;
+
+impl PPU {
+    // Advances the PPU by the given number of dots. Returns true if a new frame started along the way.
+    pub fn tick(&mut self, dots: usize) -> bool {
+        let mut frame_finished = false;
+        for _ in 0..dots {
+            frame_finished |= self.tick_dot();
+        }
+        frame_finished
+    }
+
+    fn tick_dot(&mut self) -> bool {
+        match (self.scanline, self.dot) {
+            (VBLANK_SCANLINE, 1) => {
+                self.status_register.set_vblank(true);
+                if self.control_register.is_generate_nmi() {
+                    self.nmi_pending = true;
+                }
+            },
+            (PRE_RENDER_SCANLINE, 1) => {
+                self.status_register.set_vblank(false);
+                self.status_register.set_sprite_zero_hit(false);
+                self.status_register.set_sprite_overflow(false);
+            },
+            _ => (),
+        }
+        let visible_line = self.scanline < VISIBLE_SCANLINES;
+        let pre_render_line = self.scanline == PRE_RENDER_SCANLINE;
+        if visible_line || pre_render_line {
+            self.fetch_background();
+        }
+        self.update_scroll(self.scanline, self.dot);
+        if visible_line && (1..=256).contains(&self.dot) {
+            self.draw_pixel();
+        }
+        if (visible_line || pre_render_line) && self.dot == 257 {
+            self.sprites.clear();
+            if self.mask_register.is_rendering_enabled() {
+                if visible_line {
+                    self.evaluate_sprites();
+                }
+                self.fetch_unused_sprite_slots();
+            }
+        }
+        // OAMADDR is reset while sprite tiles are fetched
+        if (visible_line || pre_render_line) && (257..=320).contains(&self.dot) && self.mask_register.is_rendering_enabled() {
+            self.oam_addr = 0;
+        }
+
+        // With rendering on, odd frames skip the last dot of the pre-render line
+        let skip_dot = self.scanline == PRE_RENDER_SCANLINE && self.dot == DOTS_PER_SCANLINE - 2
+            && self.frame % 2 == 1 && self.mask_register.is_rendering_enabled();
+        self.dot += if skip_dot { 2 } else { 1 };
+        self.cycles += 1;
+
+        if self.dot < DOTS_PER_SCANLINE {
+            return false;
+        }
+        self.dot = 0;
+        self.scanline += 1;
+        if self.scanline < SCANLINES_PER_FRAME {
+            return false;
+        }
+        self.scanline = 0;
+        self.frame += 1;
+        true
+    }
+
+    pub fn take_nmi(&mut self) -> bool {
+        std::mem::take(&mut self.nmi_pending)
+    }
+}
+
+#[cfg(test)]
+mod timing_tests {
+    use super::*;
+    use crate::Mirroring;
+
+    fn test_ppu() -> PPU {
+        PPU::from_chr_rom(vec![0; 2048], Mirroring::Horizontal)
+    }
+
+    const DOTS_PER_FRAME: usize = DOTS_PER_SCANLINE as usize * SCANLINES_PER_FRAME as usize;
+    const DOTS_TO_VBLANK: usize = DOTS_PER_SCANLINE as usize * VBLANK_SCANLINE as usize + 1;
+
+    #[test]
+    pub fn vblank_starts_at_scanline_241() {
+        let mut ppu = test_ppu();
+        ppu.tick(DOTS_TO_VBLANK);
+        assert!(!ppu.status_register.is_vblank());
+        ppu.tick(1);
+        assert!(ppu.status_register.is_vblank());
+        assert_eq!((ppu.scanline, ppu.dot), (241, 2));
+        assert!(!ppu.take_nmi());
+    }
+
+    #[test]
+    pub fn vblank_fires_nmi_when_enabled() {
+        let mut ppu = test_ppu();
+        ppu.write_to_control_register(0b1000_0000);
+        ppu.tick(DOTS_TO_VBLANK + 1);
+        assert!(ppu.take_nmi());
+        assert!(!ppu.take_nmi());
+    }
+
+    #[test]
+    pub fn enabling_nmi_during_vblank_fires_nmi() {
+        let mut ppu = test_ppu();
+        ppu.tick(DOTS_TO_VBLANK + 1);
+        ppu.write_to_control_register(0b1000_0000);
+        assert!(ppu.take_nmi());
+
+        // Rewriting PPUCTRL with NMI already enabled does not fire again
+        ppu.write_to_control_register(0b1000_0000);
+        assert!(!ppu.take_nmi());
+    }
+
+    #[test]
+    pub fn pre_render_line_clears_flags() {
+        let mut ppu = test_ppu();
+        ppu.tick(DOTS_TO_VBLANK + 1);
+        ppu.status_register.set_sprite_zero_hit(true);
+        ppu.status_register.set_sprite_overflow(true);
+        ppu.tick(DOTS_PER_SCANLINE as usize * 20);
+        assert_eq!(ppu.status_register.0, 0);
+    }
+
+    #[test]
+    pub fn frame_length() {
+        let mut ppu = test_ppu();
+        assert!(!ppu.tick(DOTS_PER_FRAME - 1));
+        assert!(ppu.tick(1));
+        assert_eq!((ppu.frame, ppu.scanline, ppu.dot), (1, 0, 0));
+    }
+
+    #[test]
+    pub fn odd_frames_skip_a_dot_when_rendering() {
+        let mut ppu = test_ppu();
+        ppu.write_to_mask(0b0000_1000);
+        ppu.tick(DOTS_PER_FRAME);
+        assert!(ppu.tick(DOTS_PER_FRAME - 1));
+        assert_eq!((ppu.frame, ppu.scanline, ppu.dot), (2, 0, 0));
+
+        // Even frames and frames with rendering off are full length
+        ppu.write_to_mask(0);
+        ppu.tick(DOTS_PER_FRAME);
+        assert!(!ppu.tick(DOTS_PER_FRAME - 1));
+    }
+}
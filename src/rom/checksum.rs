@@ -0,0 +1,121 @@
+// CRC32 and SHA-1 of the PRG and CHR ROM, the checksums header databases identify dumps by
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Checksums {
+    pub prg_crc32: u32,
+    pub chr_crc32: u32,
+    pub prg_sha1: [u8; 20],
+    pub chr_sha1: [u8; 20],
+}
+
+impl Checksums {
+    pub fn new(prg_rom: &[u8], chr_rom: &[u8]) -> Self {
+        Checksums {
+            prg_crc32: crc32(prg_rom),
+            chr_crc32: crc32(chr_rom),
+            prg_sha1: sha1(prg_rom),
+            chr_sha1: sha1(chr_rom),
+        }
+    }
+}
+
+// The reflected 0xEDB88320 polynomial used by zip and the NES 2.0 database
+const CRC32_TABLE: [u32; 256] = crc32_table();
+
+const fn crc32_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let crc = data.iter().fold(0xFFFF_FFFF, |crc, byte| {
+        CRC32_TABLE[((crc ^ *byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    });
+    !crc
+}
+
+/*
+ * SHA-1 as in FIPS 180-4. The message is padded with a 1 bit, zeros and its length in bits so it fills
+ * whole 64 byte blocks, then each block is mixed into the five state words over 80 rounds.
+ */
+pub fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x6745_2301, 0xEFCD_AB89, 0x98BA_DCFE, 0x1032_5476, 0xC3D2_E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut words = [0u32; 80];
+        for (index, word) in block.chunks_exact(4).enumerate() {
+            words[index] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for index in 16..80 {
+            words[index] = (words[index - 3] ^ words[index - 8] ^ words[index - 14] ^ words[index - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (index, word) in words.iter().enumerate() {
+            let (f, k) = match index {
+                0..=19 => ((b & c) | (!b & d), 0x5A82_7999),
+                20..=39 => (b ^ c ^ d, 0x6ED9_EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1B_BCDC),
+                _ => (b ^ c ^ d, 0xCA62_C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(*word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (word, value) in state.iter_mut().zip([a, b, c, d, e]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod checksum_tests {
+    use super::*;
+
+    fn hex(digest: &[u8]) -> String {
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    #[test]
+    pub fn crc32_check_value() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    pub fn sha1_test_vectors() {
+        assert_eq!(hex(&sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(hex(&sha1(b"abc")), "a9993e364706816aba3e25717850c26c9cd0d89d");
+        // Two blocks once padded
+        assert_eq!(
+            hex(&sha1(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq")),
+            "84983e441c3bd26ebaae4aa1f95129e5e54670f1"
+        );
+    }
+}
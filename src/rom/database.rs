@@ -0,0 +1,299 @@
+use std::{collections::HashMap, sync::OnceLock};
+
+use super::{checksum::Checksums, Timing};
+use crate::Mirroring;
+
+// The header fields a database entry can correct
+#[derive(Debug, PartialEq, Clone)]
+pub struct HeaderInfo {
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: Mirroring,
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub timing: Timing,
+}
+
+// A known dump and the header it should have had. Dumps are matched on the CRC32 of their PRG and CHR ROM,
+// the SHA-1s tell apart dumps whose CRC32s collide when the entry has them.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DbEntry {
+    pub prg_crc32: u32,
+    pub chr_crc32: u32,
+    pub prg_sha1: Option<[u8; 20]>,
+    pub chr_sha1: Option<[u8; 20]>,
+    pub header: HeaderInfo,
+}
+
+impl DbEntry {
+    fn matches(&self, checksums: &Checksums) -> bool {
+        self.prg_crc32 == checksums.prg_crc32
+            && self.chr_crc32 == checksums.chr_crc32
+            && self.prg_sha1.is_none_or(|sha1| sha1 == checksums.prg_sha1)
+            && self.chr_sha1.is_none_or(|sha1| sha1 == checksums.chr_sha1)
+    }
+
+    // Entries that pin down the dump with SHA-1s win over ones that only have CRC32s
+    fn sha1_count(&self) -> usize {
+        self.prg_sha1.is_some() as usize + self.chr_sha1.is_some() as usize
+    }
+
+    fn same_dump(&self, other: &DbEntry) -> bool {
+        (self.prg_crc32, self.chr_crc32, self.prg_sha1, self.chr_sha1)
+            == (other.prg_crc32, other.chr_crc32, other.prg_sha1, other.chr_sha1)
+    }
+}
+
+// Built-in corrections, in the format of the NES 2.0 header database (nes20db.xml)
+const BUILTIN_XML: &str = include_str!("nes20db.xml");
+
+static BUILTIN: OnceLock<RomDatabase> = OnceLock::new();
+
+// Entries are grouped by their (PRG, CHR) CRC32 pair, dumps sharing one are told apart by SHA-1
+pub struct RomDatabase {
+    entries: HashMap<(u32, u32), Vec<DbEntry>>,
+    len: usize,
+}
+
+impl Default for RomDatabase {
+    fn default() -> Self {
+        RomDatabase::new()
+    }
+}
+
+impl RomDatabase {
+    // An empty database, for callers that bring their own entries
+    pub fn new() -> Self {
+        RomDatabase { entries: HashMap::new(), len: 0 }
+    }
+
+    // Parsed on first use and shared from then on
+    pub fn builtin() -> &'static Self {
+        BUILTIN.get_or_init(|| RomDatabase::from_xml(BUILTIN_XML).expect("built-in rom database is malformed"))
+    }
+
+    /*
+     * Reads entries from NES 2.0 header database xml. Each <game> needs a <prgrom> with a crc32 and a <pcb>
+     * with the mapper, the rest defaults to what an empty NES 2.0 header says:
+     * <game>
+     *     <prgrom size="32768" crc32="..." sha1="..."/>
+     *     <chrrom size="8192" crc32="..." sha1="..."/>
+     *     <prgram size="8192"/> <prgnvram .../> <chrram .../> <chrnvram .../>
+     *     <pcb mapper="4" submapper="0" mirroring="V" battery="1"/>
+     *     <console type="0" region="0"/>
+     * </game>
+     */
+    pub fn from_xml(xml: &str) -> Result<Self, String> {
+        let mut database = RomDatabase::new();
+        for (index, game) in xml.split("<game>").skip(1).enumerate() {
+            let game = game.split("</game>").next().unwrap_or(game);
+            let entry = parse_game(game).map_err(|error| format!("game {index}: {error}"))?;
+            database.insert(entry);
+        }
+        Ok(database)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    // A later entry for the same dump replaces the earlier one
+    pub fn insert(&mut self, entry: DbEntry) {
+        let bucket = self.entries.entry((entry.prg_crc32, entry.chr_crc32)).or_default();
+        match bucket.iter_mut().find(|known| known.same_dump(&entry)) {
+            Some(known) => *known = entry,
+            None => {
+                bucket.push(entry);
+                self.len += 1;
+            },
+        }
+    }
+
+    pub fn find(&self, checksums: &Checksums) -> Option<&HeaderInfo> {
+        self.entries
+            .get(&(checksums.prg_crc32, checksums.chr_crc32))?
+            .iter()
+            .filter(|entry| entry.matches(checksums))
+            .max_by_key(|entry| entry.sha1_count())
+            .map(|entry| &entry.header)
+    }
+}
+
+// Value of attribute name on the first <tag .../> in the game, if both are there
+fn attribute<'a>(game: &'a str, tag: &str, name: &str) -> Option<&'a str> {
+    let element = game.split(&format!("<{tag} ")).nth(1)?.split('>').next()?;
+    // Values in the database never contain spaces
+    element
+        .split_whitespace()
+        .find_map(|pair| pair.strip_prefix(name)?.strip_prefix("=\"")?.split('"').next())
+}
+
+fn parse_number<T: std::str::FromStr>(game: &str, tag: &str, name: &str) -> Result<Option<T>, String> {
+    attribute(game, tag, name)
+        .map(|value| value.parse().map_err(|_| format!("bad {tag} {name} \"{value}\"")))
+        .transpose()
+}
+
+fn parse_crc32(game: &str, tag: &str) -> Result<Option<u32>, String> {
+    attribute(game, tag, "crc32")
+        .map(|value| u32::from_str_radix(value, 16).map_err(|_| format!("bad {tag} crc32 \"{value}\"")))
+        .transpose()
+}
+
+fn parse_sha1(game: &str, tag: &str) -> Result<Option<[u8; 20]>, String> {
+    let Some(value) = attribute(game, tag, "sha1") else {
+        return Ok(None);
+    };
+    let error = || format!("bad {tag} sha1 \"{value}\"");
+    if value.len() != 40 || !value.is_ascii() {
+        return Err(error());
+    }
+    let mut sha1 = [0; 20];
+    for (index, byte) in sha1.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&value[index * 2..index * 2 + 2], 16).map_err(|_| error())?;
+    }
+    Ok(Some(sha1))
+}
+
+fn parse_game(game: &str) -> Result<DbEntry, String> {
+    let size = |tag| parse_number::<usize>(game, tag, "size").map(Option::unwrap_or_default);
+    Ok(DbEntry {
+        prg_crc32: parse_crc32(game, "prgrom")?.ok_or("missing prgrom crc32")?,
+        // Roms without CHR ROM have nothing to checksum, the CRC32 of no data is 0
+        chr_crc32: parse_crc32(game, "chrrom")?.unwrap_or(0),
+        prg_sha1: parse_sha1(game, "prgrom")?,
+        chr_sha1: parse_sha1(game, "chrrom")?,
+        header: HeaderInfo {
+            mapper: parse_number(game, "pcb", "mapper")?.ok_or("missing pcb mapper")?,
+            submapper: parse_number(game, "pcb", "submapper")?.unwrap_or(0),
+            mirroring: match attribute(game, "pcb", "mirroring") {
+                Some("V") => Mirroring::Vertical,
+                Some("4") => Mirroring::FourScreen,
+                _ => Mirroring::Horizontal,
+            },
+            prg_ram_size: size("prgram")?,
+            prg_nvram_size: size("prgnvram")?,
+            chr_ram_size: size("chrram")?,
+            chr_nvram_size: size("chrnvram")?,
+            timing: match parse_number::<u8>(game, "console", "region")?.unwrap_or(0) {
+                0 => Timing::Ntsc,
+                1 => Timing::Pal,
+                2 => Timing::MultiRegion,
+                _ => Timing::Dendy,
+            },
+        },
+    })
+}
+
+#[cfg(test)]
+mod database_tests {
+    use super::*;
+
+    fn test_header(mapper: u16) -> HeaderInfo {
+        HeaderInfo {
+            mapper,
+            submapper: 0,
+            mirroring: Mirroring::Vertical,
+            prg_ram_size: 0,
+            prg_nvram_size: 0,
+            chr_ram_size: 0,
+            chr_nvram_size: 0,
+            timing: Timing::Ntsc,
+        }
+    }
+
+    fn test_entry(mapper: u16, prg_sha1: Option<[u8; 20]>) -> DbEntry {
+        DbEntry { prg_crc32: 1, chr_crc32: 2, prg_sha1, chr_sha1: None, header: test_header(mapper) }
+    }
+
+    #[test]
+    pub fn find_matches_both_checksums() {
+        let mut database = RomDatabase::new();
+        database.insert(test_entry(4, None));
+        let mut checksums = Checksums { prg_crc32: 1, chr_crc32: 2, ..Checksums::default() };
+        assert_eq!(database.find(&checksums).map(|header| header.mapper), Some(4));
+        checksums.chr_crc32 = 0;
+        assert!(database.find(&checksums).is_none());
+    }
+
+    #[test]
+    pub fn insert_replaces_entry() {
+        let mut database = RomDatabase::new();
+        database.insert(test_entry(4, None));
+        database.insert(test_entry(1, None));
+        assert_eq!(database.len(), 1);
+        let checksums = Checksums { prg_crc32: 1, chr_crc32: 2, ..Checksums::default() };
+        assert_eq!(database.find(&checksums).map(|header| header.mapper), Some(1));
+    }
+
+    #[test]
+    pub fn sha1_breaks_crc_ties() {
+        let mut database = RomDatabase::new();
+        database.insert(test_entry(2, None));
+        database.insert(test_entry(4, Some([0xAA; 20])));
+        database.insert(test_entry(1, Some([0xBB; 20])));
+        database.insert(test_entry(1, Some([0xBB; 20])));
+        assert_eq!(database.len(), 3);
+        let mut checksums = Checksums { prg_crc32: 1, chr_crc32: 2, prg_sha1: [0xBB; 20], ..Checksums::default() };
+        assert_eq!(database.find(&checksums).map(|header| header.mapper), Some(1));
+        // Without a SHA-1 match the CRC32 only entry is the best there is
+        checksums.prg_sha1 = [0xCC; 20];
+        assert_eq!(database.find(&checksums).map(|header| header.mapper), Some(2));
+    }
+
+    #[test]
+    pub fn reads_nes20db_xml() {
+        let xml = r#"<nes20db>
+<game>
+    <!-- Some Game (USA) -->
+    <prgrom size="131072" crc32="0000ABCD" sha1="0102030405060708090A0B0C0D0E0F1011121314"/>
+    <chrrom size="131072" crc32="1234FFFF"/>
+    <prgnvram size="8192"/>
+    <pcb mapper="4" submapper="1" mirroring="4" battery="1"/>
+    <console type="0" region="1"/>
+</game>
+<game>
+    <prgrom size="32768" crc32="00000002"/>
+    <chrram size="8192"/>
+    <pcb mapper="2" mirroring="V"/>
+</game>
+</nes20db>"#;
+        let database = RomDatabase::from_xml(xml).unwrap();
+        assert_eq!(database.len(), 2);
+
+        let mut prg_sha1 = [0; 20];
+        for (index, byte) in prg_sha1.iter_mut().enumerate() {
+            *byte = index as u8 + 1;
+        }
+        let checksums = Checksums { prg_crc32: 0xABCD, chr_crc32: 0x1234_FFFF, prg_sha1, ..Checksums::default() };
+        assert_eq!(database.find(&checksums), Some(&HeaderInfo {
+            mapper: 4,
+            submapper: 1,
+            mirroring: Mirroring::FourScreen,
+            prg_ram_size: 0,
+            prg_nvram_size: 8192,
+            chr_ram_size: 0,
+            chr_nvram_size: 0,
+            timing: Timing::Pal,
+        }));
+
+        let checksums = Checksums { prg_crc32: 2, chr_crc32: 0, ..Checksums::default() };
+        let header = database.find(&checksums).unwrap();
+        assert_eq!((header.mapper, header.chr_ram_size), (2, 8192));
+        assert!(header.mirroring == Mirroring::Vertical);
+
+        assert!(RomDatabase::from_xml("<game><pcb mapper=\"1\"/></game>").is_err());
+        assert!(RomDatabase::from_xml("<game><prgrom crc32=\"XYZ\"/><pcb mapper=\"1\"/></game>").is_err());
+    }
+
+    #[test]
+    pub fn builtin_database_parses() {
+        assert!(std::ptr::eq(RomDatabase::builtin(), RomDatabase::builtin()));
+    }
+}
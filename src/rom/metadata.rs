@@ -0,0 +1,45 @@
+// CPU and PPU timing the game was made for
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Timing {
+    #[default]
+    Ntsc,
+    Pal,
+    // Runs on either, the game detects the region itself
+    MultiRegion,
+    Dendy,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ConsoleType {
+    #[default]
+    Nes,
+    // Vs. System arcade board, with its PPU model and protection hardware
+    VsSystem { ppu_type: u8, hardware_type: u8 },
+    Playchoice10,
+    // NES 2.0 extended console types such as Famiclones and the VT chips, numbered as in the header
+    Extended(u8),
+}
+
+/*
+ * Everything the header says about the cartridge besides the ROM data itself. iNES 1.0 headers only have
+ * room for some of it, the rest is filled in with what iNES 1.0 roms can be assumed to have.
+ * Memory sizes are in bytes.
+ */
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RomMetadata {
+    pub is_nes2: bool,
+    pub has_battery: bool,
+    // 512 bytes of extra code meant for $7000, see Rom::trainer
+    pub has_trainer: bool,
+    // Volatile and battery backed PRG-RAM at $6000 - $7FFF
+    pub prg_ram_size: usize,
+    pub prg_nvram_size: usize,
+    pub chr_ram_size: usize,
+    pub chr_nvram_size: usize,
+    pub timing: Timing,
+    pub console_type: ConsoleType,
+    // Controller or other device plugged in by default, numbered as in the NES 2.0 header
+    pub expansion_device: u8,
+    // Extra ROM chips stored after CHR ROM
+    pub misc_rom_count: u8,
+}
@@ -1,31 +1,78 @@
-use crate::Mirroring;
+pub mod checksum;
+pub mod database;
+mod metadata;
+
+use std::{fs::File, io::Read, path::Path};
+
+use checksum::Checksums;
+use database::{HeaderInfo, RomDatabase};
+pub use metadata::{ConsoleType, RomMetadata, Timing};
+
+use crate::{error::RomError, mapper, Mirroring};
 
 // TODO update these with the actual values later
 const NES_TAG: [u8; 4] = [0x4E, 0x45, 0x53, 0x1A];
+const HEADER_SIZE: usize = 16;
 const PGR_ROM_PAGE_SIZE: usize = 16384;
 const CHR_ROM_PAGE_SIZE: usize = 8192;
+const TRAINER_SIZE: usize = 512;
+// iNES 1.0 roms without CHR ROM and without a PRG-RAM size get the usual 8KB
+const DEFAULT_RAM_SIZE: usize = 8192;
 
 pub struct Rom {
     pub prg_rom: Vec<u8>,
     pub chr_rom: Vec<u8>,
-    pub mapper: u8,
-    pub screen_mirroring: Mirroring
+    // 12 bits on NES 2.0, 8 bits on iNES 1.0
+    pub mapper: u16,
+    // Picks between boards that share a mapper number but wire it differently, 0 when the header doesn't say
+    pub submapper: u8,
+    pub screen_mirroring: Mirroring,
+    pub metadata: RomMetadata,
+    // Copied into PRG-RAM at $7000 - $71FF on power-up. Mostly found in hacked and translated dumps.
+    pub trainer: Option<Vec<u8>>,
+    pub checksums: Checksums,
+    // What the header said, when a database entry for this dump replaced it
+    pub original_header: Option<HeaderInfo>,
 }
 
 impl Rom {
-    pub fn new(raw: &Vec<u8>) -> Result<Rom, String> {
-        if &raw[0..4] != NES_TAG {
-            return Err(String::from("File is not in the iNES file format"));
+    pub fn new(raw: &[u8]) -> Result<Rom, RomError> {
+        Rom::with_database(raw, RomDatabase::builtin())
+    }
+
+    // Parses the rom, then corrects its header from the database if the dump is in there. Roms for mappers
+    // the emulator can't run are rejected here rather than when the cartridge is built.
+    pub fn with_database(raw: &[u8], database: &RomDatabase) -> Result<Rom, RomError> {
+        let rom = Rom::parse(raw, database)?;
+        if !mapper::is_supported(rom.mapper) {
+            return Err(RomError::UnsupportedMapper { mapper: rom.mapper, submapper: rom.submapper });
         }
+        mapper::check_prg_size(rom.mapper, rom.prg_rom.len())?;
+        Ok(rom)
+    }
 
-        // Take the MSB of raw[7] and combine it with the LSB of raw[6]
-        let mapper = (raw[7] & 0b1111_0000) | (raw[6] >> 4);
+    fn parse(raw: &[u8], database: &RomDatabase) -> Result<Rom, RomError> {
+        if raw.len() < NES_TAG.len() || raw[0..4] != NES_TAG {
+            return Err(RomError::BadMagic);
+        }
+        if raw.len() < HEADER_SIZE {
+            return Err(RomError::TruncatedHeader { len: raw.len() });
+        }
 
         // Read bits (3,2). If 10, then iNES 2.0 format, if 00 then iNES 1.0 format
         let ines_ver = (raw[7] >> 2) & 0b11;
-        if ines_ver != 0 {
-            return Err(String::from("iNES2.0 format is not supported"));
-        }
+        let is_nes2 = ines_ver == 0b10;
+
+        // Take the MSB of raw[7] and combine it with the LSB of raw[6]. Old dumps with any other version
+        // bits often have garbage in bytes 7 - 15, so only the low nibble can be trusted there.
+        let mapper = match ines_ver {
+            0b00 | 0b10 => ((raw[7] & 0b1111_0000) | (raw[6] >> 4)) as u16,
+            _ => (raw[6] >> 4) as u16,
+        };
+        let (mapper, submapper) = match is_nes2 {
+            true => (mapper | (((raw[8] & 0x0F) as u16) << 8), raw[8] >> 4),
+            false => (mapper, 0),
+        };
 
         let four_screen = raw[6] & 0b0000_1000 != 0;
         let vertical_mirroring = raw[6] & 0b0000_0001 != 0;
@@ -35,25 +82,181 @@ impl Rom {
             (false, false) => Mirroring::Horizontal,
         };
 
-        let prg_rom_size = raw[4] as usize * PGR_ROM_PAGE_SIZE;
-        let chr_rom_size = raw[5] as usize * CHR_ROM_PAGE_SIZE;
+        let (prg_rom_size, chr_rom_size, metadata) = match is_nes2 {
+            true => (
+                nes2_rom_size(raw[4], raw[9] & 0x0F, PGR_ROM_PAGE_SIZE)?,
+                nes2_rom_size(raw[5], raw[9] >> 4, CHR_ROM_PAGE_SIZE)?,
+                nes2_metadata(raw),
+            ),
+            false => {
+                let chr_rom_size = raw[5] as usize * CHR_ROM_PAGE_SIZE;
+                (raw[4] as usize * PGR_ROM_PAGE_SIZE, chr_rom_size, ines_metadata(raw, chr_rom_size))
+            },
+        };
 
-        let skip_trainer = raw[6] & 0b0000_0100 != 0;
+        if prg_rom_size == 0 {
+            return Err(RomError::EmptyPrgRom);
+        }
 
-        let prg_rom_start = 16 + if skip_trainer { 512 } else { 0 };
+        let prg_rom_start = HEADER_SIZE + if metadata.has_trainer { TRAINER_SIZE } else { 0 };
+        if raw.len() < prg_rom_start {
+            return Err(RomError::TruncatedTrainer);
+        }
+        // Sizes are compared against what is left of the file, so huge NES 2.0 sizes can't overflow
+        if raw.len() - prg_rom_start < prg_rom_size {
+            return Err(RomError::TruncatedPrgRom { expected: prg_rom_size, actual: raw.len() - prg_rom_start });
+        }
         let chr_rom_start = prg_rom_start + prg_rom_size;
+        if raw.len() - chr_rom_start < chr_rom_size {
+            return Err(RomError::TruncatedChrRom { expected: chr_rom_size, actual: raw.len() - chr_rom_start });
+        }
+        let chr_rom_end = chr_rom_start + chr_rom_size;
+        // Misc ROMs and the PlayChoice-10 hint screen data live after CHR ROM. NES 2.0 headers declare them, so
+        // anything else there is suspect. Plenty of iNES 1.0 dumps carry a title block at the end, which is fine.
+        let allows_trailing_data = !metadata.is_nes2
+            || metadata.misc_rom_count > 0
+            || metadata.console_type == ConsoleType::Playchoice10;
+        if raw.len() > chr_rom_end && !allows_trailing_data {
+            return Err(RomError::TrailingData { len: raw.len() - chr_rom_end });
+        }
 
-        Ok(Rom {
-            prg_rom: raw[prg_rom_start..(prg_rom_start + prg_rom_size)].to_vec(),
-            chr_rom: raw[chr_rom_start..(chr_rom_start + chr_rom_size)].to_vec(),
+        let prg_rom = &raw[prg_rom_start..chr_rom_start];
+        let chr_rom = &raw[chr_rom_start..chr_rom_end];
+        let mut rom = Rom {
+            prg_rom: prg_rom.to_vec(),
+            chr_rom: chr_rom.to_vec(),
             mapper,
-            screen_mirroring
-        })
+            submapper,
+            screen_mirroring,
+            trainer: match metadata.has_trainer {
+                true => Some(raw[HEADER_SIZE..prg_rom_start].to_vec()),
+                false => None,
+            },
+            metadata,
+            checksums: Checksums::new(prg_rom, chr_rom),
+            original_header: None,
+        };
+        if let Some(header) = database.find(&rom.checksums) {
+            rom.apply_header(header.clone());
+        }
+        Ok(rom)
+    }
+
+    pub fn header_info(&self) -> HeaderInfo {
+        HeaderInfo {
+            mapper: self.mapper,
+            submapper: self.submapper,
+            mirroring: self.screen_mirroring.clone(),
+            prg_ram_size: self.metadata.prg_ram_size,
+            prg_nvram_size: self.metadata.prg_nvram_size,
+            chr_ram_size: self.metadata.chr_ram_size,
+            chr_nvram_size: self.metadata.chr_nvram_size,
+            timing: self.metadata.timing,
+        }
+    }
+
+    // Overrides the header fields, keeping the old ones in original_header if anything changed
+    fn apply_header(&mut self, header: HeaderInfo) {
+        let original = self.header_info();
+        if original == header {
+            return;
+        }
+        self.mapper = header.mapper;
+        self.submapper = header.submapper;
+        self.screen_mirroring = header.mirroring;
+        self.metadata.prg_ram_size = header.prg_ram_size;
+        self.metadata.prg_nvram_size = header.prg_nvram_size;
+        self.metadata.chr_ram_size = header.chr_ram_size;
+        self.metadata.chr_nvram_size = header.chr_nvram_size;
+        self.metadata.timing = header.timing;
+        // Database entries are full NES 2.0 headers, so the RAM sizes can be trusted like one
+        self.metadata.is_nes2 = true;
+        self.metadata.has_battery = header.prg_nvram_size + header.chr_nvram_size > 0;
+        self.original_header = Some(original);
     }
 
-    pub fn from_rom(path: &str) -> Result<Rom, String> {
-        let file = std::fs::read(path).unwrap();
-        Rom::new(&file)
+    pub fn from_rom(path: impl AsRef<Path>) -> Result<Rom, RomError> {
+        Rom::from_reader(File::open(path)?)
+    }
+
+    pub fn from_reader(mut reader: impl Read) -> Result<Rom, RomError> {
+        let mut raw = Vec::new();
+        reader.read_to_end(&mut raw)?;
+        Rom::new(&raw)
+    }
+}
+
+// NES 2.0 sizes are a 12 bit count of pages, unless the top nibble is $F. Then the low byte is an exponent
+// and multiplier instead: EEEEEEMM is 2^E * (MM * 2 + 1) bytes. Exponents that don't fit in a usize are an error.
+fn nes2_rom_size(lsb: u8, msb: u8, page_size: usize) -> Result<usize, RomError> {
+    let size = match msb {
+        0x0F => 1usize
+            .checked_shl((lsb >> 2) as u32)
+            .and_then(|size| size.checked_mul((lsb & 0b11) as usize * 2 + 1)),
+        _ => (((msb as usize) << 8) | lsb as usize).checked_mul(page_size),
+    };
+    size.ok_or(RomError::InvalidRomSize { lsb, msb })
+}
+
+// RAM sizes are stored as a shift count, 64 << shift bytes or nothing when it is 0
+fn nes2_ram_size(shift: u8) -> usize {
+    match shift {
+        0 => 0,
+        _ => 64 << shift,
+    }
+}
+
+fn console_type(raw: &[u8], is_nes2: bool) -> ConsoleType {
+    match raw[7] & 0b11 {
+        0 => ConsoleType::Nes,
+        1 if is_nes2 => ConsoleType::VsSystem { ppu_type: raw[13] & 0x0F, hardware_type: raw[13] >> 4 },
+        1 => ConsoleType::VsSystem { ppu_type: 0, hardware_type: 0 },
+        2 => ConsoleType::Playchoice10,
+        _ => ConsoleType::Extended(raw[13] & 0x0F),
+    }
+}
+
+fn nes2_metadata(raw: &[u8]) -> RomMetadata {
+    RomMetadata {
+        is_nes2: true,
+        has_battery: raw[6] & 0b0000_0010 != 0,
+        has_trainer: raw[6] & 0b0000_0100 != 0,
+        prg_ram_size: nes2_ram_size(raw[10] & 0x0F),
+        prg_nvram_size: nes2_ram_size(raw[10] >> 4),
+        chr_ram_size: nes2_ram_size(raw[11] & 0x0F),
+        chr_nvram_size: nes2_ram_size(raw[11] >> 4),
+        timing: match raw[12] & 0b11 {
+            0 => Timing::Ntsc,
+            1 => Timing::Pal,
+            2 => Timing::MultiRegion,
+            _ => Timing::Dendy,
+        },
+        console_type: console_type(raw, true),
+        expansion_device: raw[15] & 0b11_1111,
+        misc_rom_count: raw[14] & 0b11,
+    }
+}
+
+// iNES 1.0 only knows about the battery, an optional PRG-RAM size in 8KB units and a rarely set PAL bit.
+// The RAM is all battery backed or all volatile.
+fn ines_metadata(raw: &[u8], chr_rom_size: usize) -> RomMetadata {
+    let has_battery = raw[6] & 0b0000_0010 != 0;
+    let prg_ram_size = match raw[8] {
+        0 => DEFAULT_RAM_SIZE,
+        pages => pages as usize * DEFAULT_RAM_SIZE,
+    };
+    RomMetadata {
+        is_nes2: false,
+        has_battery,
+        has_trainer: raw[6] & 0b0000_0100 != 0,
+        prg_ram_size: if has_battery { 0 } else { prg_ram_size },
+        prg_nvram_size: if has_battery { prg_ram_size } else { 0 },
+        chr_ram_size: if chr_rom_size == 0 { DEFAULT_RAM_SIZE } else { 0 },
+        chr_nvram_size: 0,
+        timing: if raw[9] & 1 != 0 { Timing::Pal } else { Timing::Ntsc },
+        console_type: console_type(raw, false),
+        expansion_device: 0,
+        misc_rom_count: 0,
     }
 }
 
@@ -82,18 +285,237 @@ mod rom_constructor_test {
         let result = Rom::new(&tester);
         match result {
             Ok(_) => panic!("Should have been an error"),
-            Err(str) => assert_eq!(str, "File is not in the iNES file format")
+            Err(error) => assert_eq!(error.to_string(), "File is not in the iNES file format")
         }
+        assert!(matches!(Rom::new(&NES_TAG), Err(RomError::TruncatedHeader { len: 4 })));
     }
 
     #[test]
-    pub fn ines_ver_incorrect() {
+    pub fn truncated_roms() {
         let mut tester = get_test_raw();
-        tester[7] = 0b0000_1000;
+        tester.truncate(16 + PGR_ROM_PAGE_SIZE + 10);
+        assert!(matches!(
+            Rom::new(&tester),
+            Err(RomError::TruncatedChrRom { expected: CHR_ROM_PAGE_SIZE, actual: 10 })
+        ));
+        tester.truncate(16 + 10);
+        assert!(matches!(
+            Rom::new(&tester),
+            Err(RomError::TruncatedPrgRom { expected: PGR_ROM_PAGE_SIZE, actual: 10 })
+        ));
+
+        // The trainer flag adds 512 bytes before PRG ROM
+        tester.truncate(16);
+        tester[6] = 0b0000_0100;
+        assert!(matches!(Rom::new(&tester), Err(RomError::TruncatedTrainer)));
+
+        tester[4] = 0;
+        assert!(matches!(Rom::new(&tester), Err(RomError::EmptyPrgRom)));
+    }
+
+    #[test]
+    pub fn unsupported_mapper() {
+        let mut tester = get_test_raw();
+        tester[6] = 0xF0;
+        tester[7] = 0xF0;
         match Rom::new(&tester) {
             Ok(_) => panic!("Should have been an error"),
-            Err(str) => assert_eq!(str, "iNES2.0 format is not supported"),
-        } 
+            Err(error) => assert_eq!(error.to_string(), "Mapper 255 is not supported"),
+        }
+    }
+
+    #[test]
+    pub fn partial_prg_bank() {
+        // NES 2.0 MMC1 header with 2^10 * 3 bytes of PRG ROM
+        let mut tester = get_test_raw();
+        tester[6] = 0b0001_0000;
+        tester[7] = 0b0000_1000;
+        tester[4] = 0b0010_1001;
+        tester[9] = 0x0F;
+        tester.drain(16 + 3072..16 + PGR_ROM_PAGE_SIZE);
+        assert!(matches!(Rom::new(&tester), Err(RomError::InvalidPrgSize { mapper: 1, len: 3072 })));
+
+        // UxROM
+        tester[6] = 0b0010_0000;
+        assert!(matches!(Rom::new(&tester), Err(RomError::InvalidPrgSize { mapper: 2, len: 3072 })));
+    }
+
+    #[test]
+    pub fn trailing_data() {
+        let mut tester = get_test_raw();
+        tester.extend_from_slice(b"Title");
+        assert!(Rom::new(&tester).is_ok());
+
+        tester[7] = 0b0000_1000;
+        assert!(matches!(Rom::new(&tester), Err(RomError::TrailingData { len: 5 })));
+
+        // A NES 2.0 header that declares misc ROMs accounts for it
+        tester[14] = 1;
+        assert_eq!(Rom::new(&tester).unwrap().metadata.misc_rom_count, 1);
+    }
+
+    #[test]
+    pub fn from_reader() {
+        let tester = get_test_raw();
+        let rom = Rom::from_reader(tester.as_slice()).unwrap();
+        assert_eq!(rom.prg_rom.len(), PGR_ROM_PAGE_SIZE);
+        assert!(matches!(Rom::from_rom("./does-not-exist.nes"), Err(RomError::Io(_))));
+    }
+
+    #[test]
+    pub fn nes2_header() {
+        let mut tester = get_test_raw();
+        tester[6] = 0b0101_0010;
+        tester[7] = 0b1010_1001;
+        tester[8] = 0b0011_0001;
+        tester[10] = 0x70;
+        tester[11] = 0x07;
+        tester[12] = 0x01;
+        tester[13] = 0x21;
+        tester[14] = 0x01;
+        tester[15] = 0x03;
+        // No board is emulated for mapper 421
+        assert!(matches!(Rom::new(&tester), Err(RomError::UnsupportedMapper { mapper: 0x1A5, submapper: 3 })));
+        let rom = Rom::parse(&tester, &RomDatabase::new()).unwrap();
+        assert_eq!(rom.mapper, 0x1A5);
+        assert_eq!(rom.submapper, 3);
+        assert_eq!(rom.prg_rom.len(), PGR_ROM_PAGE_SIZE);
+        assert_eq!(rom.chr_rom.len(), CHR_ROM_PAGE_SIZE);
+        assert_eq!(rom.metadata, RomMetadata {
+            is_nes2: true,
+            has_battery: true,
+            has_trainer: false,
+            prg_ram_size: 0,
+            prg_nvram_size: 8192,
+            chr_ram_size: 8192,
+            chr_nvram_size: 0,
+            timing: Timing::Pal,
+            console_type: ConsoleType::VsSystem { ppu_type: 1, hardware_type: 2 },
+            expansion_device: 3,
+            misc_rom_count: 1,
+        });
+    }
+
+    #[test]
+    pub fn oversized_nes2_roms() {
+        let mut tester = get_test_raw();
+        tester[7] = 0b0000_1000;
+        // PRG ROM with exponent 63 and multiplier 7
+        tester[4] = 0xFF;
+        tester[9] = 0x0F;
+        assert!(matches!(Rom::new(&tester), Err(RomError::InvalidRomSize { lsb: 0xFF, msb: 0x0F })));
+
+        // CHR ROM with exponent 62, which fits in a usize but not in the file
+        tester[4] = 1;
+        tester[5] = 0xF8;
+        tester[9] = 0xF0;
+        assert!(matches!(Rom::new(&tester), Err(RomError::TruncatedChrRom { expected: 0x4000_0000_0000_0000, .. })));
+        tester[5] = 0xFF;
+        assert!(matches!(Rom::new(&tester), Err(RomError::InvalidRomSize { lsb: 0xFF, msb: 0x0F })));
+    }
+
+    #[test]
+    pub fn nes2_rom_sizes() {
+        assert_eq!(nes2_rom_size(2, 0, PGR_ROM_PAGE_SIZE).unwrap(), 2 * PGR_ROM_PAGE_SIZE);
+        assert_eq!(nes2_rom_size(0x00, 0x1, CHR_ROM_PAGE_SIZE).unwrap(), 256 * CHR_ROM_PAGE_SIZE);
+        // Exponent-multiplier: 2^10 * 3
+        assert_eq!(nes2_rom_size(0b0010_1001, 0xF, PGR_ROM_PAGE_SIZE).unwrap(), 3072);
+        // 2^63 * 7 doesn't fit
+        assert!(matches!(nes2_rom_size(0xFF, 0xF, PGR_ROM_PAGE_SIZE), Err(RomError::InvalidRomSize { lsb: 0xFF, msb: 0xF })));
+        assert_eq!(nes2_ram_size(0), 0);
+        assert_eq!(nes2_ram_size(7), 8192);
+    }
+
+    #[test]
+    pub fn ines_defaults() {
+        let mut tester = get_test_raw();
+        tester[5] = 0;
+        tester[6] = 0b0001_0010;
+        tester.truncate(16 + PGR_ROM_PAGE_SIZE);
+        let rom = Rom::new(&tester).unwrap();
+        assert_eq!(rom.mapper, 1);
+        assert!(!rom.metadata.is_nes2);
+        assert_eq!(rom.metadata.prg_nvram_size, 8192);
+        assert_eq!(rom.metadata.prg_ram_size, 0);
+        assert_eq!(rom.metadata.chr_ram_size, 8192);
+    }
+
+    // Dumps with "DiskDude!" or similar tags in the padding keep only the low mapper nibble
+    #[test]
+    pub fn archaic_ines_header() {
+        let mut tester = get_test_raw();
+        tester[6] = 0b0010_0000;
+        tester[7..16].copy_from_slice(b"DiskDude!");
+        let rom = Rom::new(&tester).unwrap();
+        assert_eq!(rom.mapper, 2);
+    }
+
+    #[test]
+    pub fn trainer_is_kept() {
+        let mut tester = get_test_raw();
+        tester[6] = 0b0000_0100;
+        let mut trainer = vec![0xEA; TRAINER_SIZE];
+        trainer[0] = 0x4C;
+        tester.splice(16..16, trainer);
+        let rom = Rom::new(&tester).unwrap();
+        assert!(rom.metadata.has_trainer);
+        let trainer = rom.trainer.unwrap();
+        assert_eq!((trainer.len(), trainer[0], trainer[1]), (TRAINER_SIZE, 0x4C, 0xEA));
+        assert_eq!(rom.prg_rom, vec![0; PGR_ROM_PAGE_SIZE]);
+
+        assert!(Rom::new(&get_test_raw()).unwrap().trainer.is_none());
+    }
+
+    #[test]
+    pub fn database_corrects_header() {
+        let mut tester = get_test_raw();
+        tester[4] = 8;
+        tester.resize(16 + 8 * PGR_ROM_PAGE_SIZE + CHR_ROM_PAGE_SIZE, 0);
+        tester[16] = 0x42;
+        let rom = Rom::new(&tester).unwrap();
+        assert_eq!(rom.checksums.prg_crc32, checksum::crc32(&tester[16..16 + 8 * PGR_ROM_PAGE_SIZE]));
+        assert!(rom.original_header.is_none());
+
+        // The dump says NROM with horizontal mirroring, the database knows it is an MMC1 board with battery RAM
+        let xml = format!(
+            r#"<game>
+    <prgrom size="131072" crc32="{:08X}"/>
+    <chrrom size="8192" crc32="{:08X}"/>
+    <prgnvram size="8192"/>
+    <pcb mapper="1" submapper="0" mirroring="V" battery="1"/>
+    <console type="0" region="1"/>
+</game>"#,
+            rom.checksums.prg_crc32, rom.checksums.chr_crc32
+        );
+        let database = RomDatabase::from_xml(&xml).unwrap();
+        let corrected = Rom::with_database(&tester, &database).unwrap();
+        assert_eq!((corrected.mapper, corrected.submapper), (1, 0));
+        assert_eq!(corrected.screen_mirroring, Mirroring::Vertical);
+        assert_eq!(corrected.metadata.prg_ram_size, 0);
+        assert_eq!(corrected.metadata.prg_nvram_size, 8192);
+        assert_eq!(corrected.metadata.chr_ram_size, 0);
+        assert!(corrected.metadata.has_battery);
+        assert_eq!(corrected.metadata.timing, Timing::Pal);
+        assert_eq!(corrected.original_header, Some(rom.header_info()));
+
+        // A header the database can fix isn't rejected for its wrong mapper
+        tester[6] = 0xF0;
+        tester[7] = 0xF0;
+        assert!(Rom::new(&tester).is_err());
+        assert_eq!(Rom::with_database(&tester, &database).unwrap().mapper, 1);
+
+        // An entry that agrees with the header isn't reported
+        let mut database = RomDatabase::new();
+        database.insert(database::DbEntry {
+            prg_crc32: rom.checksums.prg_crc32,
+            chr_crc32: rom.checksums.chr_crc32,
+            prg_sha1: Some(rom.checksums.prg_sha1),
+            chr_sha1: None,
+            header: rom.header_info(),
+        });
+        tester[6] = 0;
+        tester[7] = 0;
+        assert!(Rom::with_database(&tester, &database).unwrap().original_header.is_none());
     }
 
     #[test]